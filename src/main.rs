@@ -1,3 +1,4 @@
+#[cfg_attr(not(test), allow(dead_code, unused_macros))]
 mod yams;
 
 fn main() {
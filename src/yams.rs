@@ -1,150 +1,3240 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::sync::LazyLock;
 
 type Dice = [u8; 5];
 
+macro_rules! dice {
+    ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr $(,)?) => {{
+        const DICE: [u8; 5] = [$a, $b, $c, $d, $e];
+        const {
+            assert!(
+                $crate::yams::dice_in_range(&DICE),
+                "die values must be between 1 and 6"
+            )
+        };
+        DICE
+    }};
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CombinationResult {
     Matched(u32),
     NotMatched,
 }
 
-type CombinationChecker = fn(&Dice) -> CombinationResult;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Combination {
+    FourOfAKind,
+    FullHouse,
+    ThreeOfAKind,
+    Straight,
+    Chance,
+}
 
-fn calculate_yams_total_score(rounds: &[Dice]) -> u32 {
-    rounds
-        .iter()
-        .map(|dice| calculate_yams_round_score(dice))
-        .sum()
+impl fmt::Display for Combination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", combination_info(*self).name)
+    }
 }
 
-fn calculate_yams_round_score(dice: &Dice) -> u32 {
-    let cases: Vec<CombinationChecker> = vec![
-        check_four_of_a_kind,
-        check_full_house,
-        check_three_of_a_kind,
-        check_straight,
-    ];
-    for case in cases {
-        if let CombinationResult::Matched(score) = case(dice) {
-            return score;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StraightKind {
+    Low,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CombinationInfo {
+    name: &'static str,
+    fixed_score: Option<u32>,
+    description: &'static str,
+}
+
+fn combination_info(combination: Combination) -> CombinationInfo {
+    let (name, description) = match combination {
+        Combination::FourOfAKind => (
+            "Four of a kind",
+            "At least four dice showing the same face.",
+        ),
+        Combination::FullHouse => ("Full house", "Three dice of one face and two of another."),
+        Combination::ThreeOfAKind => (
+            "Three of a kind",
+            "At least three dice showing the same face.",
+        ),
+        Combination::Straight => ("Straight", "Five consecutive faces: 1 to 5 or 2 to 6."),
+        Combination::Chance => ("Chance", "Any roll; scores the sum of the dice."),
+    };
+    CombinationInfo {
+        name,
+        fixed_score: fixed_score(combination),
+        description,
+    }
+}
+
+impl Combination {
+    fn score_range(&self, config: &ScoringConfig) -> (u32, u32) {
+        let chance_minimum = config
+            .chance_minimum
+            .map_or(5, |minimum| (minimum as u32).max(5));
+        fixed_score(*self).map_or((chance_minimum, 30), |score| (score, score))
+    }
+
+    fn implies(&self) -> &'static [Combination] {
+        match self {
+            Combination::FourOfAKind | Combination::FullHouse => {
+                &[Combination::ThreeOfAKind, Combination::Chance]
+            }
+            Combination::ThreeOfAKind | Combination::Straight => &[Combination::Chance],
+            Combination::Chance => &[],
         }
     }
 
-    calculate_chance_score(dice)
+    fn possible_scores(&self, config: &ScoringConfig) -> Vec<u32> {
+        let mut scores: Vec<u32> = all_roll_multisets()
+            .iter()
+            .filter_map(|dice| match check_with_config(*self, dice, config) {
+                CombinationResult::Matched(score) => Some(score),
+                CombinationResult::NotMatched => None,
+            })
+            .collect();
+        scores.sort();
+        scores.dedup();
+        scores
+    }
 }
 
-fn check_four_of_a_kind(dice: &Dice) -> CombinationResult {
-    if contains_four_of_a_kind(dice) {
-        CombinationResult::Matched(35)
-    } else {
-        CombinationResult::NotMatched
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ScoringConfig {
+    chance_minimum: Option<u8>,
+    straight_requires_all_dice: bool,
+    full_house_allows_yams: bool,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            chance_minimum: None,
+            straight_requires_all_dice: true,
+            full_house_allows_yams: false,
+        }
     }
 }
 
-fn contains_four_of_a_kind(dice: &Dice) -> bool {
-    for die in dice {
-        if dice.iter().filter(|x| *x == die).count() >= 4 {
-            return true;
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Ruleset {
+    combinations: Vec<Combination>,
+    rerolls_per_turn: u8,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset::with_combinations(&ORDERED_COMBINATIONS)
+    }
+}
+
+impl Ruleset {
+    fn with_combinations(combinations: &[Combination]) -> Self {
+        Ruleset {
+            combinations: combinations.to_vec(),
+            rerolls_per_turn: REROLLS_PER_TURN,
         }
     }
-    false
 }
 
-fn check_full_house(dice: &Dice) -> CombinationResult {
-    if contains_full_house(dice) {
-        CombinationResult::Matched(30)
-    } else {
-        CombinationResult::NotMatched
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GameResult {
+    total: u32,
+    rounds: Vec<Dice>,
+    breakdown: Vec<Option<(Combination, u32)>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JokerDie {
+    Value(u8),
+    Joker,
+}
+
+type JokerDice = [JokerDie; 5];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Die(u8);
+
+#[derive(Debug, PartialEq, Eq)]
+enum DiceError {
+    OutOfRange(u8),
+}
+
+impl fmt::Display for DiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiceError::OutOfRange(value) => write!(f, "die value {value} is not between 1 and 6"),
+        }
     }
 }
 
-fn contains_full_house(dice: &Dice) -> bool {
-    let mut counts = HashMap::new();
-    for die in dice {
-        *counts.entry(die).or_insert(0) += 1;
+impl std::error::Error for DiceError {}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ParseDiceError {
+    WrongCount { found: usize },
+    OutOfRange { value: u8, position: usize },
+    NotANumber { token: String },
+}
+
+impl fmt::Display for ParseDiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseDiceError::WrongCount { found } => write!(f, "expected 5 dice, found {found}"),
+            ParseDiceError::OutOfRange { value, position } => write!(
+                f,
+                "die {} has value {value}, which is not between 1 and 6",
+                position + 1
+            ),
+            ParseDiceError::NotANumber { token } => write!(f, "{token:?} is not a die value"),
+        }
     }
-    counts.values().any(|&count| count == 3) && counts.values().any(|&count| count == 2)
 }
 
-fn check_three_of_a_kind(dice: &Dice) -> CombinationResult {
-    if contains_three_of_a_kind(dice) {
-        CombinationResult::Matched(28)
-    } else {
-        CombinationResult::NotMatched
+impl std::error::Error for ParseDiceError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Turn {
+    rolls: Vec<Dice>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TurnError {
+    NoRolls {
+        turn: usize,
+    },
+    TooManyRerolls {
+        turn: usize,
+        rerolls: usize,
+        allowed: u8,
+    },
+}
+
+impl fmt::Display for TurnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TurnError::NoRolls { turn } => write!(f, "turn {turn} has no rolls"),
+            TurnError::TooManyRerolls {
+                turn,
+                rerolls,
+                allowed,
+            } => write!(
+                f,
+                "turn {turn} has {rerolls} rerolls, more than the {allowed} allowed"
+            ),
+        }
     }
 }
 
-fn contains_three_of_a_kind(dice: &Dice) -> bool {
-    for die in dice {
-        if dice.iter().filter(|x| *x == die).count() >= 3 {
-            return true;
+impl std::error::Error for TurnError {}
+
+#[derive(Debug, PartialEq, Eq)]
+struct OrderLengthError {
+    rounds: usize,
+    order: usize,
+}
+
+impl fmt::Display for OrderLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} combinations ordered for {} rounds",
+            self.order, self.rounds
+        )
+    }
+}
+
+impl std::error::Error for OrderLengthError {}
+
+#[derive(Debug, PartialEq, Eq)]
+enum WeightError {
+    WrongCount { rounds: usize, weights: usize },
+    NotFinite { round: usize },
+}
+
+impl fmt::Display for WeightError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WeightError::WrongCount { rounds, weights } => {
+                write!(f, "{weights} weights given for {rounds} rounds")
+            }
+            WeightError::NotFinite { round } => {
+                write!(f, "the weight of round {} is not finite", round + 1)
+            }
         }
     }
-    false
 }
 
-fn check_straight(dice: &Dice) -> CombinationResult {
-    if contains_straight(dice) {
-        CombinationResult::Matched(40)
-    } else {
-        CombinationResult::NotMatched
+impl std::error::Error for WeightError {}
+
+impl Die {
+    fn new(value: u8) -> Result<Die, DiceError> {
+        if (1..=6).contains(&value) {
+            Ok(Die(value))
+        } else {
+            Err(DiceError::OutOfRange(value))
+        }
+    }
+
+    fn value(&self) -> u8 {
+        self.0
     }
 }
 
-fn contains_straight(dice: &Dice) -> bool {
-    let mut sorted_dice = dice.clone();
-    sorted_dice.sort();
+impl TryFrom<u8> for Die {
+    type Error = DiceError;
 
-    sorted_dice == [1, 2, 3, 4, 5] || sorted_dice == [2, 3, 4, 5, 6]
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Die::new(value)
+    }
 }
 
-fn calculate_chance_score(dice: &Dice) -> u32 {
-    let sum: u8 = dice.iter().sum();
-    sum as u32
+fn validate_dice(dice: &Dice) -> Result<[Die; 5], DiceError> {
+    let mut dies = [Die(1); 5];
+    for (die, value) in dies.iter_mut().zip(dice) {
+        *die = Die::try_from(*value)?;
+    }
+    Ok(dies)
 }
 
-#[cfg(test)]
-mod test {
-    use super::calculate_yams_round_score;
+fn parse_dice(input: &str) -> Result<Dice, ParseDiceError> {
+    let values = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            token.parse::<u8>().map_err(|_| ParseDiceError::NotANumber {
+                token: token.to_string(),
+            })
+        })
+        .collect::<Result<Vec<u8>, _>>()?;
+    let dice: Dice = values
+        .as_slice()
+        .try_into()
+        .map_err(|_| ParseDiceError::WrongCount {
+            found: values.len(),
+        })?;
+    match dice.iter().position(|value| Die::new(*value).is_err()) {
+        Some(position) => Err(ParseDiceError::OutOfRange {
+            value: dice[position],
+            position,
+        }),
+        None => Ok(dice),
+    }
+}
 
-    #[test]
-    fn test_three_of_a_kind() {
-        let dice = [3, 3, 3, 2, 5];
-        assert_eq!(calculate_yams_round_score(&dice), 28);
+fn dice_from_dies(dies: &[Die; 5]) -> Dice {
+    dies.map(|die| die.value())
+}
+
+type FaceCounts = [u8; 7];
+
+const ORDERED_COMBINATIONS: [Combination; 5] = [
+    Combination::FourOfAKind,
+    Combination::FullHouse,
+    Combination::ThreeOfAKind,
+    Combination::Straight,
+    Combination::Chance,
+];
+
+const REROLLS_PER_TURN: u8 = 2;
+
+const DECISION_MARGIN: u32 = 5;
+
+type RollOutcome = (Vec<u8>, f64);
+
+static ROLL_OUTCOMES: LazyLock<Vec<Vec<RollOutcome>>> =
+    LazyLock::new(|| (0..=5).map(roll_outcomes).collect());
+
+static COMBINATION_TABLE: LazyLock<HashMap<Dice, [CombinationResult; 5]>> = LazyLock::new(|| {
+    all_roll_multisets()
+        .into_iter()
+        .map(|dice| {
+            (
+                dice,
+                ORDERED_COMBINATIONS.map(|combination| check(combination, &dice)),
+            )
+        })
+        .collect()
+});
+
+fn calculate_yams_total_score(rounds: &[Dice]) -> u32 {
+    calculate_yams_total_score_with_hook(rounds, &ScoringConfig::default(), |_, _| {})
+}
+
+fn calculate_yams_game(rounds: &[Dice], config: &ScoringConfig) -> GameResult {
+    let breakdown: Vec<Option<(Combination, u32)>> = rounds
+        .iter()
+        .map(|dice| calculate_yams_round_result_with_config(dice, &ORDERED_COMBINATIONS, config))
+        .collect();
+    let total = breakdown_total(&breakdown);
+    GameResult {
+        total,
+        rounds: rounds.to_vec(),
+        breakdown,
     }
+}
 
-    #[test]
-    fn test_four_of_a_kind() {
-        let dice = [4, 4, 4, 4, 1];
-        assert_eq!(calculate_yams_round_score(&dice), 35);
+fn breakdown_total(breakdown: &[Option<(Combination, u32)>]) -> u32 {
+    breakdown.iter().flatten().map(|(_, score)| score).sum()
+}
+
+fn max_possible_game_score(rounds: usize) -> u32 {
+    let best_round = ORDERED_COMBINATIONS
+        .iter()
+        .map(|combination| combination.score_range(&ScoringConfig::default()).1)
+        .max()
+        .unwrap_or(0);
+    rounds as u32 * best_round
+}
+
+/// A lower bound: whatever is rolled, each open combination is eventually
+/// filled with at least its worst roll's score. The bound is 5 if Chance is
+/// open with no minimum above 5, and 0 otherwise.
+fn guaranteed_remaining_score(remaining: &[Combination], config: &ScoringConfig) -> u32 {
+    let rolls = all_roll_multisets();
+    remaining
+        .iter()
+        .map(|combination| {
+            rolls
+                .iter()
+                .map(|dice| match check_with_config(*combination, dice, config) {
+                    CombinationResult::Matched(score) => score,
+                    CombinationResult::NotMatched => 0,
+                })
+                .min()
+                .unwrap_or(0)
+        })
+        .sum()
+}
+
+fn game_efficiency(result: &GameResult) -> f64 {
+    let max_score = max_possible_game_score(result.breakdown.len());
+    if max_score == 0 {
+        return 0.0;
     }
+    100.0 * result.total as f64 / max_score as f64
+}
 
-    #[test]
-    fn test_full_house() {
-        let dice = [2, 2, 3, 3, 3];
-        assert_eq!(calculate_yams_round_score(&dice), 30);
+fn summarize_game(result: &GameResult) -> String {
+    let mut summary = format!(
+        "Total: {} points over {} rounds.",
+        result.total,
+        result.breakdown.len()
+    );
+
+    let round_scores: Vec<u32> = result
+        .breakdown
+        .iter()
+        .map(|round| round.map_or(0, |(_, score)| score))
+        .collect();
+    let best = (0..round_scores.len()).max_by_key(|&index| (round_scores[index], Reverse(index)));
+    let worst = (0..round_scores.len()).min_by_key(|&index| (round_scores[index], index));
+    if let (Some(best), Some(worst)) = (best, worst) {
+        summary += &format!(
+            " Best round: {} ({}). Worst round: {} ({}).",
+            best + 1,
+            describe_round(&result.breakdown[best]),
+            worst + 1,
+            describe_round(&result.breakdown[worst])
+        );
     }
 
-    #[test]
-    fn test_straight() {
-        let dice = [1, 2, 3, 4, 5];
-        assert_eq!(calculate_yams_round_score(&dice), 40);
+    let usage_points = |combination: Combination| -> u32 {
+        result
+            .breakdown
+            .iter()
+            .flatten()
+            .filter(|(used, _)| *used == combination)
+            .map(|(_, score)| score)
+            .sum()
+    };
+    if let Some(top) = ORDERED_COMBINATIONS
+        .iter()
+        .copied()
+        .filter(|combination| usage_points(*combination) > 0)
+        .max_by_key(|combination| (usage_points(*combination), Reverse(*combination as usize)))
+    {
+        summary += &format!(
+            " Highest-scoring combination: {top} ({} points).",
+            usage_points(top)
+        );
     }
+    summary
+}
 
-    #[test]
-    fn test_chance() {
-        let dice = [1, 2, 3, 4, 6];
-        assert_eq!(calculate_yams_round_score(&dice), 16);
+fn describe_round(round: &Option<(Combination, u32)>) -> String {
+    match round {
+        Some((combination, score)) => format!("{combination}, {score} points"),
+        None => "no score".to_string(),
     }
+}
 
-    #[test]
-    fn test_yams_total_score() {
-        let rounds = vec![
-            [3, 3, 3, 2, 5], // Three of a kind: 28
-            [4, 4, 4, 4, 1], // Four of a kind: 35
-            [2, 2, 3, 3, 3], // Full house: 30
-            [1, 2, 3, 4, 5], // Straight: 40
-            [1, 2, 3, 4, 6], // Chance: 16
-        ];
-        assert_eq!(super::calculate_yams_total_score(&rounds), 149);
+fn game_to_csv(result: &GameResult) -> String {
+    let mut csv = String::from("round,dice,combination,score\n");
+    for (index, (dice, round)) in result.rounds.iter().zip(&result.breakdown).enumerate() {
+        let dice = dice.map(|die| die.to_string()).join(" ");
+        let (combination, score) = match round {
+            Some((combination, score)) => (combination.to_string(), *score),
+            None => (String::new(), 0),
+        };
+        csv += &format!("{},{dice},{combination},{score}\n", index + 1);
+    }
+    csv
+}
+
+fn calculate_yams_total_score_with_turns(
+    turns: &[Turn],
+    ruleset: &Ruleset,
+) -> Result<u32, TurnError> {
+    let mut final_rolls = Vec::with_capacity(turns.len());
+    for (turn, Turn { rolls }) in turns.iter().enumerate() {
+        let Some(final_roll) = rolls.last() else {
+            return Err(TurnError::NoRolls { turn });
+        };
+        let rerolls = rolls.len() - 1;
+        if rerolls > ruleset.rerolls_per_turn as usize {
+            return Err(TurnError::TooManyRerolls {
+                turn,
+                rerolls,
+                allowed: ruleset.rerolls_per_turn,
+            });
+        }
+        final_rolls.push(*final_roll);
+    }
+    Ok(calculate_yams_total_score_with_ruleset(
+        &final_rolls,
+        ruleset,
+    ))
+}
+
+fn calculate_yams_weighted_score(rounds: &[Dice], weights: &[f64]) -> Result<f64, WeightError> {
+    if rounds.len() != weights.len() {
+        return Err(WeightError::WrongCount {
+            rounds: rounds.len(),
+            weights: weights.len(),
+        });
+    }
+    if let Some(round) = weights.iter().position(|weight| !weight.is_finite()) {
+        return Err(WeightError::NotFinite { round });
+    }
+    Ok(rounds
+        .iter()
+        .zip(weights)
+        .map(|(dice, weight)| calculate_yams_round_score(dice) as f64 * weight)
+        .sum())
+}
+
+fn calculate_yams_total_score_with_ruleset(rounds: &[Dice], ruleset: &Ruleset) -> u32 {
+    rounds
+        .iter()
+        .map(|dice| best_available_score(dice, &ruleset.combinations))
+        .sum()
+}
+
+fn calculate_yams_total_score_with_hook(
+    rounds: &[Dice],
+    config: &ScoringConfig,
+    mut hook: impl FnMut(usize, Option<(Combination, u32)>),
+) -> u32 {
+    rounds
+        .iter()
+        .enumerate()
+        .map(|(index, dice)| {
+            let result =
+                calculate_yams_round_result_with_config(dice, &ORDERED_COMBINATIONS, config);
+            hook(index, result);
+            result.map_or(0, |(_, score)| score)
+        })
+        .sum()
+}
+
+#[derive(Debug, Default)]
+struct ScoreAccumulator {
+    total: u32,
+}
+
+impl ScoreAccumulator {
+    fn add(&mut self, dice: &Dice) -> (Combination, u32) {
+        let result = calculate_yams_round_result(dice);
+        self.total += result.1;
+        result
+    }
+
+    fn total(&self) -> u32 {
+        self.total
+    }
+}
+
+/// Each combination is used at most once; a round with no matching
+/// remaining combination scores 0 and consumes nothing.
+fn calculate_yams_total_score_skip(rounds: &[Dice]) -> u32 {
+    greedy_skip_breakdown(rounds)
+        .iter()
+        .flatten()
+        .map(|(_, score)| score)
+        .sum()
+}
+
+fn greedy_skip_breakdown(rounds: &[Dice]) -> Vec<Option<(Combination, u32)>> {
+    let mut remaining = ORDERED_COMBINATIONS.to_vec();
+    rounds
+        .iter()
+        .map(|dice| {
+            let result = calculate_yams_round_result_among(dice, &remaining)?;
+            remaining.retain(|available| *available != result.0);
+            Some(result)
+        })
+        .collect()
+}
+
+/// Each combination is used at most once; a round with no matching
+/// remaining combination scores 0 and scratches the remaining combination
+/// with the lowest maximum score.
+fn calculate_yams_total_score_scratch(rounds: &[Dice]) -> u32 {
+    let mut remaining = ORDERED_COMBINATIONS.to_vec();
+    rounds
+        .iter()
+        .map(|dice| {
+            score_consuming_round(dice, &mut remaining).unwrap_or_else(|| {
+                if let Some(scratched) = remaining
+                    .iter()
+                    .copied()
+                    .min_by_key(|combination| combination.score_range(&ScoringConfig::default()).1)
+                {
+                    remaining.retain(|combination| *combination != scratched);
+                }
+                0
+            })
+        })
+        .sum()
+}
+
+fn score_consuming_round(dice: &Dice, remaining: &mut Vec<Combination>) -> Option<u32> {
+    let (combination, score) = calculate_yams_round_result_among(dice, remaining)?;
+    remaining.retain(|available| *available != combination);
+    Some(score)
+}
+
+fn calculate_yams_optimal_score(rounds: &[Dice]) -> u32 {
+    optimal_breakdown(rounds)
+        .iter()
+        .flatten()
+        .map(|(_, score)| score)
+        .sum()
+}
+
+fn optimal_breakdown(rounds: &[Dice]) -> Vec<Option<(Combination, u32)>> {
+    type Assignment = (u32, Vec<Option<(Combination, u32)>>);
+
+    let mut best_by_used: Vec<Option<Assignment>> = vec![None; 1 << ORDERED_COMBINATIONS.len()];
+    best_by_used[0] = Some((0, Vec::new()));
+    for dice in rounds {
+        let mut next: Vec<Option<Assignment>> = best_by_used
+            .iter()
+            .map(|state| {
+                state.as_ref().map(|(total, breakdown)| {
+                    let mut breakdown = breakdown.clone();
+                    breakdown.push(None);
+                    (*total, breakdown)
+                })
+            })
+            .collect();
+        for (used, state) in best_by_used.iter().enumerate() {
+            let Some((total, breakdown)) = state else {
+                continue;
+            };
+            for (index, combination) in ORDERED_COMBINATIONS.iter().enumerate() {
+                if used & (1 << index) == 0
+                    && let CombinationResult::Matched(score) = check(*combination, dice)
+                {
+                    let entry = &mut next[used | (1 << index)];
+                    if entry.as_ref().is_none_or(|(best, _)| total + score > *best) {
+                        let mut breakdown = breakdown.clone();
+                        breakdown.push(Some((*combination, score)));
+                        *entry = Some((total + score, breakdown));
+                    }
+                }
+            }
+        }
+        best_by_used = next;
+    }
+    best_by_used
+        .into_iter()
+        .flatten()
+        .reduce(|best, candidate| {
+            if candidate.0 > best.0 {
+                candidate
+            } else {
+                best
+            }
+        })
+        .map(|(_, breakdown)| breakdown)
+        .unwrap_or_default()
+}
+
+/// A round where greedy scored more than the optimal breakdown counts as 0
+/// regret rather than a negative one, so the rounds can sum to more than
+/// `wasted_points`.
+fn greedy_regret_per_round(rounds: &[Dice]) -> Vec<u32> {
+    let round_score = |round: &Option<(Combination, u32)>| round.map_or(0, |(_, score)| score);
+    optimal_breakdown(rounds)
+        .iter()
+        .zip(greedy_skip_breakdown(rounds).iter())
+        .map(|(optimal, greedy)| round_score(optimal).saturating_sub(round_score(greedy)))
+        .collect()
+}
+
+fn wasted_points(rounds: &[Dice]) -> u32 {
+    calculate_yams_optimal_score(rounds) - calculate_yams_total_score_skip(rounds)
+}
+
+fn calculate_yams_forced_order_score(
+    rounds: &[Dice],
+    order: &[Combination],
+) -> Result<u32, OrderLengthError> {
+    if rounds.len() != order.len() {
+        return Err(OrderLengthError {
+            rounds: rounds.len(),
+            order: order.len(),
+        });
+    }
+    Ok(rounds
+        .iter()
+        .zip(order)
+        .map(|(dice, combination)| score_or_zero(*combination, dice))
+        .sum())
+}
+
+fn roll_quality(dice: &Dice, remaining: &[Combination]) -> f64 {
+    let Some(max_score) = remaining
+        .iter()
+        .map(|combination| combination.score_range(&ScoringConfig::default()).1)
+        .max()
+    else {
+        return 0.0;
+    };
+    best_available_score(dice, remaining) as f64 / max_score as f64
+}
+
+fn combination_usage(breakdown: &[Option<(Combination, u32)>]) -> HashMap<Combination, u32> {
+    let mut usage = HashMap::new();
+    for (combination, _) in breakdown.iter().flatten() {
+        *usage.entry(*combination).or_insert(0) += 1;
+    }
+    usage
+}
+
+fn calculate_yams_round_score(dice: &Dice) -> u32 {
+    calculate_yams_round_result(dice).1
+}
+
+fn calculate_yams_round_result(dice: &Dice) -> (Combination, u32) {
+    let cases = [
+        Combination::FourOfAKind,
+        Combination::FullHouse,
+        Combination::ThreeOfAKind,
+        Combination::Straight,
+    ];
+    for combination in cases {
+        if let CombinationResult::Matched(score) = check(combination, dice) {
+            return (combination, score);
+        }
+    }
+
+    (Combination::Chance, calculate_chance_score(dice))
+}
+
+fn fixed_score(combination: Combination) -> Option<u32> {
+    match combination {
+        Combination::FourOfAKind => Some(35),
+        Combination::FullHouse => Some(30),
+        Combination::ThreeOfAKind => Some(28),
+        Combination::Straight => Some(40),
+        Combination::Chance => None,
+    }
+}
+
+fn check(combination: Combination, dice: &Dice) -> CombinationResult {
+    check_with_config(combination, dice, &ScoringConfig::default())
+}
+
+fn score_or_zero(combination: Combination, dice: &Dice) -> u32 {
+    match check(combination, dice) {
+        CombinationResult::Matched(score) => score,
+        CombinationResult::NotMatched => 0,
+    }
+}
+
+fn check_with_config(
+    combination: Combination,
+    dice: &Dice,
+    config: &ScoringConfig,
+) -> CombinationResult {
+    match combination {
+        Combination::FourOfAKind => check_four_of_a_kind(dice),
+        Combination::FullHouse => check_full_house(dice, config),
+        Combination::ThreeOfAKind => check_three_of_a_kind(dice),
+        Combination::Straight => check_straight(dice, config),
+        Combination::Chance => check_chance(dice, config),
+    }
+}
+
+fn calculate_yams_round_result_with_config(
+    dice: &Dice,
+    available: &[Combination],
+    config: &ScoringConfig,
+) -> Option<(Combination, u32)> {
+    best_matching(available, |combination| {
+        check_with_config(combination, dice, config)
+    })
+}
+
+fn score_round_multi(dice: &Dice, configs: &[ScoringConfig]) -> Vec<Option<(Combination, u32)>> {
+    configs
+        .iter()
+        .map(|config| calculate_yams_round_result_with_config(dice, &ORDERED_COMBINATIONS, config))
+        .collect()
+}
+
+fn calculate_yams_round_result_among(
+    dice: &Dice,
+    available: &[Combination],
+) -> Option<(Combination, u32)> {
+    if available.is_empty() {
+        return None;
+    }
+
+    let Some(results) = COMBINATION_TABLE.get(&canonicalize(dice)) else {
+        return best_matching(available, |combination| check(combination, dice));
+    };
+    best_matching(available, |combination| {
+        let index = ORDERED_COMBINATIONS
+            .iter()
+            .position(|ordered| *ordered == combination)
+            .expect("every combination is in ORDERED_COMBINATIONS");
+        results[index]
+    })
+}
+
+fn second_best(dice: &Dice, remaining: &[Combination]) -> Option<(Combination, u32)> {
+    top_k_cases(&matching_cases(dice, remaining), 2)
+        .get(1)
+        .copied()
+}
+
+fn matching_cases(dice: &Dice, remaining: &[Combination]) -> Vec<(Combination, u32)> {
+    remaining
+        .iter()
+        .filter_map(|combination| match check(*combination, dice) {
+            CombinationResult::Matched(score) => Some((*combination, score)),
+            CombinationResult::NotMatched => None,
+        })
+        .collect()
+}
+
+fn legal_plays(dice: &Dice, remaining: &[Combination]) -> Vec<(Combination, u32)> {
+    remaining
+        .iter()
+        .map(|combination| (*combination, score_or_zero(*combination, dice)))
+        .collect()
+}
+
+fn is_decision_point(dice: &Dice, remaining: &[Combination]) -> bool {
+    let mut scores: Vec<u32> = matching_cases(dice, remaining)
+        .iter()
+        .map(|(_, score)| *score)
+        .collect();
+    scores.sort();
+    scores
+        .windows(2)
+        .any(|pair| pair[1] - pair[0] <= DECISION_MARGIN)
+}
+
+fn best_cases(cases: &[(Combination, u32)]) -> (u32, Vec<Combination>) {
+    let best_score = cases.iter().map(|(_, score)| *score).max().unwrap_or(0);
+    let combinations = cases
+        .iter()
+        .filter(|(_, score)| *score == best_score)
+        .map(|(combination, _)| *combination)
+        .collect();
+    (best_score, combinations)
+}
+
+fn top_k_cases(cases: &[(Combination, u32)], k: usize) -> Vec<(Combination, u32)> {
+    if k == 0 {
+        return Vec::new();
+    }
+
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for (index, (_, score)) in cases.iter().enumerate() {
+        heap.push(Reverse((*score, Reverse(index))));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((_, Reverse(index)))| cases[index])
+        .collect()
+}
+
+fn best_of(window: &[Dice], remaining: &[Combination]) -> Option<(usize, Combination, u32)> {
+    let mut best: Option<(usize, Combination, u32)> = None;
+    for (index, dice) in window.iter().enumerate() {
+        if let Some((combination, score)) = calculate_yams_round_result_among(dice, remaining)
+            && best.is_none_or(|(_, _, best_score)| score > best_score)
+        {
+            best = Some((index, combination, score));
+        }
+    }
+    best
+}
+
+fn strategically_equivalent(a: &Dice, b: &Dice, remaining: &[Combination]) -> bool {
+    remaining
+        .iter()
+        .all(|combination| check(*combination, a) == check(*combination, b))
+}
+
+fn score_round_from_counts(
+    counts: &FaceCounts,
+    remaining: &[Combination],
+) -> Option<(Combination, u32)> {
+    best_matching(remaining, |combination| check_counts(combination, counts))
+}
+
+fn calculate_yams_round_result_with_tie_break(
+    dice: &Dice,
+    available: &[Combination],
+    tie_break: &dyn TieBreak,
+) -> Option<(Combination, u32)> {
+    best_matching_with(available, |combination| check(combination, dice), tie_break)
+}
+
+fn score_with_extra_die(
+    dice: &Dice,
+    extra: Die,
+    remaining: &[Combination],
+) -> Option<(Combination, u32)> {
+    let mut counts = [0; 7];
+    for die in dice.iter().chain([&extra.value()]) {
+        counts[*die as usize] += 1;
+    }
+    score_round_from_counts(&counts, remaining)
+}
+
+fn best_matching(
+    available: &[Combination],
+    check: impl Fn(Combination) -> CombinationResult,
+) -> Option<(Combination, u32)> {
+    best_matching_with(available, check, &PreferFirst)
+}
+
+fn best_matching_with(
+    available: &[Combination],
+    check: impl Fn(Combination) -> CombinationResult,
+    tie_break: &dyn TieBreak,
+) -> Option<(Combination, u32)> {
+    let mut best: Option<(Combination, u32)> = None;
+    for combination in available {
+        if let CombinationResult::Matched(score) = check(*combination) {
+            let candidate = (*combination, score);
+            best = Some(match best {
+                Some(current) if score < current.1 => current,
+                Some(current) if score == current.1 => tie_break.pick(current, candidate),
+                _ => candidate,
+            });
+        }
+    }
+    best
+}
+
+trait TieBreak {
+    fn pick(&self, a: (Combination, u32), b: (Combination, u32)) -> (Combination, u32);
+}
+
+struct PreferFirst;
+
+impl TieBreak for PreferFirst {
+    fn pick(&self, a: (Combination, u32), _b: (Combination, u32)) -> (Combination, u32) {
+        a
+    }
+}
+
+struct PreferHigherValue;
+
+impl TieBreak for PreferHigherValue {
+    fn pick(&self, a: (Combination, u32), b: (Combination, u32)) -> (Combination, u32) {
+        let config = ScoringConfig::default();
+        if b.0.score_range(&config).1 > a.0.score_range(&config).1 {
+            b
+        } else {
+            a
+        }
+    }
+}
+
+struct PreferLowerValue;
+
+impl TieBreak for PreferLowerValue {
+    fn pick(&self, a: (Combination, u32), b: (Combination, u32)) -> (Combination, u32) {
+        let config = ScoringConfig::default();
+        if b.0.score_range(&config).1 < a.0.score_range(&config).1 {
+            b
+        } else {
+            a
+        }
+    }
+}
+
+fn check_counts(combination: Combination, counts: &FaceCounts) -> CombinationResult {
+    let faces = &counts[1..];
+    let matched = match combination {
+        Combination::FourOfAKind => faces.iter().any(|&count| count >= 4),
+        Combination::FullHouse => (1..=6)
+            .any(|three| counts[three] >= 3 && (1..=6).any(|two| two != three && counts[two] >= 2)),
+        Combination::ThreeOfAKind => faces.iter().any(|&count| count >= 3),
+        Combination::Straight => {
+            counts[1..=5].iter().all(|&count| count >= 1)
+                || counts[2..=6].iter().all(|&count| count >= 1)
+        }
+        Combination::Chance => true,
+    };
+    if !matched {
+        return CombinationResult::NotMatched;
+    }
+
+    CombinationResult::Matched(fixed_score(combination).unwrap_or_else(|| {
+        counts
+            .iter()
+            .enumerate()
+            .map(|(face, &count)| face as u32 * count as u32)
+            .sum()
+    }))
+}
+
+fn best_available_score(dice: &Dice, remaining: &[Combination]) -> u32 {
+    calculate_yams_round_result_among(dice, remaining).map_or(0, |(_, score)| score)
+}
+
+fn check_four_of_a_kind(dice: &Dice) -> CombinationResult {
+    if contains_four_of_a_kind(dice) {
+        CombinationResult::Matched(35)
+    } else {
+        CombinationResult::NotMatched
+    }
+}
+
+fn contains_four_of_a_kind(dice: &Dice) -> bool {
+    for die in dice {
+        if dice.iter().filter(|x| *x == die).count() >= 4 {
+            return true;
+        }
+    }
+    false
+}
+
+fn check_full_house(dice: &Dice, config: &ScoringConfig) -> CombinationResult {
+    let is_yams = dice.iter().all(|die| *die == dice[0]);
+    if contains_full_house(dice) || (config.full_house_allows_yams && is_yams) {
+        CombinationResult::Matched(30)
+    } else {
+        CombinationResult::NotMatched
+    }
+}
+
+fn contains_full_house(dice: &Dice) -> bool {
+    let mut counts = HashMap::new();
+    for die in dice {
+        *counts.entry(die).or_insert(0) += 1;
+    }
+    counts.values().any(|&count| count == 3) && counts.values().any(|&count| count == 2)
+}
+
+fn check_three_of_a_kind(dice: &Dice) -> CombinationResult {
+    if contains_three_of_a_kind(dice) {
+        CombinationResult::Matched(28)
+    } else {
+        CombinationResult::NotMatched
+    }
+}
+
+fn contains_three_of_a_kind(dice: &Dice) -> bool {
+    for die in dice {
+        if dice.iter().filter(|x| *x == die).count() >= 3 {
+            return true;
+        }
+    }
+    false
+}
+
+fn check_straight(dice: &Dice, config: &ScoringConfig) -> CombinationResult {
+    let matched = if config.straight_requires_all_dice {
+        contains_straight(dice)
+    } else {
+        contains_run(dice, dice.len() - 1)
+    };
+    if matched {
+        CombinationResult::Matched(40)
+    } else {
+        CombinationResult::NotMatched
+    }
+}
+
+fn contains_straight(dice: &Dice) -> bool {
+    straight_kind(dice).is_some()
+}
+
+fn straight_kind(dice: &Dice) -> Option<StraightKind> {
+    match canonicalize(dice) {
+        [1, 2, 3, 4, 5] => Some(StraightKind::Low),
+        [2, 3, 4, 5, 6] => Some(StraightKind::High),
+        _ => None,
+    }
+}
+
+fn contains_run(dice: &Dice, length: usize) -> bool {
+    let mut faces = dice.to_vec();
+    faces.sort();
+    faces.dedup();
+    faces
+        .windows(length)
+        .any(|window| window.windows(2).all(|pair| pair[1] == pair[0] + 1))
+}
+
+const fn dice_in_range(dice: &Dice) -> bool {
+    let mut position = 0;
+    while position < dice.len() {
+        if dice[position] < 1 || dice[position] > 6 {
+            return false;
+        }
+        position += 1;
+    }
+    true
+}
+
+fn canonicalize(dice: &Dice) -> Dice {
+    let mut sorted_dice = *dice;
+    sorted_dice.sort();
+    sorted_dice
+}
+
+fn sort_for_display(dice: &Dice, combination: Combination) -> Dice {
+    let mut sorted_dice = canonicalize(dice);
+    match combination {
+        Combination::FourOfAKind | Combination::FullHouse | Combination::ThreeOfAKind => {
+            let count = |face: &u8| dice.iter().filter(|die| *die == face).count();
+            sorted_dice.sort_by_key(|face| (Reverse(count(face)), *face));
+        }
+        Combination::Straight | Combination::Chance => {}
+    }
+    sorted_dice
+}
+
+fn check_chance(dice: &Dice, config: &ScoringConfig) -> CombinationResult {
+    let score = calculate_chance_score(dice);
+    match config.chance_minimum {
+        Some(minimum) if score < minimum as u32 => CombinationResult::NotMatched,
+        _ => CombinationResult::Matched(score),
+    }
+}
+
+fn calculate_chance_score(dice: &Dice) -> u32 {
+    let sum: u8 = dice.iter().sum();
+    sum as u32
+}
+
+fn expected_chance_score(dice_count: usize, sides: u8) -> f64 {
+    dice_count as f64 * (sides as f64 + 1.0) / 2.0
+}
+
+fn expected_greedy_round_score() -> f64 {
+    let rolls = all_rolls();
+    let total: u32 = rolls.iter().map(calculate_yams_round_score).sum();
+    total as f64 / rolls.len() as f64
+}
+
+fn combination_frequencies() -> HashMap<Combination, f64> {
+    combination_frequencies_sorted().into_iter().collect()
+}
+
+fn combination_frequencies_sorted() -> Vec<(Combination, f64)> {
+    let rolls = all_rolls();
+    ORDERED_COMBINATIONS
+        .iter()
+        .map(|combination| {
+            let matching = rolls
+                .iter()
+                .filter(|dice| check(*combination, dice) != CombinationResult::NotMatched)
+                .count();
+            (*combination, matching as f64 / rolls.len() as f64)
+        })
+        .collect()
+}
+
+fn combination_frequencies_weighted(face_probs: &[f64; 6]) -> HashMap<Combination, f64> {
+    let rolls: Vec<(Dice, f64)> = all_rolls()
+        .into_iter()
+        .map(|dice| {
+            let probability = dice
+                .iter()
+                .map(|die| face_probs[*die as usize - 1])
+                .product();
+            (dice, probability)
+        })
+        .collect();
+    ORDERED_COMBINATIONS
+        .iter()
+        .map(|combination| {
+            let probability = rolls
+                .iter()
+                .filter(|(dice, _)| check(*combination, dice) != CombinationResult::NotMatched)
+                .map(|(_, probability)| probability)
+                .sum();
+            (*combination, probability)
+        })
+        .collect()
+}
+
+fn combinations_by_rarity() -> Vec<(Combination, f64)> {
+    let mut frequencies = combination_frequencies_sorted();
+    frequencies.sort_by(|a, b| a.1.total_cmp(&b.1));
+    frequencies
+}
+
+fn best_combination_distribution() -> HashMap<Combination, f64> {
+    let rolls = all_rolls();
+    let mut distribution = HashMap::new();
+    for dice in &rolls {
+        let (combination, _) = calculate_yams_round_result(dice);
+        *distribution.entry(combination).or_insert(0.0) += 1.0 / rolls.len() as f64;
+    }
+    distribution
+}
+
+fn optimal_fill_order() -> Vec<Combination> {
+    let mut roll_probabilities: HashMap<Dice, f64> = HashMap::new();
+    let rolls = all_rolls();
+    for dice in &rolls {
+        *roll_probabilities.entry(canonicalize(dice)).or_insert(0.0) += 1.0 / rolls.len() as f64;
+    }
+    let mut multisets: Vec<(Dice, f64)> = roll_probabilities.into_iter().collect();
+    multisets.sort_by_key(|(dice, _)| *dice);
+
+    let mut best: Option<(Vec<Combination>, f64)> = None;
+    for order in fill_orders(&ORDERED_COMBINATIONS) {
+        let expected = expected_priority_game_score(&order, &multisets);
+        if best
+            .as_ref()
+            .is_none_or(|(_, best_expected)| expected > *best_expected)
+        {
+            best = Some((order, expected));
+        }
+    }
+    best.expect("there is always at least one fill order").0
+}
+
+fn fill_orders(combinations: &[Combination]) -> Vec<Vec<Combination>> {
+    if combinations.is_empty() {
+        return vec![vec![]];
+    }
+    let mut orders = Vec::new();
+    for (index, first) in combinations.iter().enumerate() {
+        let mut rest = combinations.to_vec();
+        rest.remove(index);
+        for mut order in fill_orders(&rest) {
+            order.insert(0, *first);
+            orders.push(order);
+        }
+    }
+    orders
+}
+
+/// Each turn takes the highest-priority open combination the roll matches,
+/// or scratches the lowest-priority one when none match.
+fn expected_priority_game_score(order: &[Combination], rolls: &[(Dice, f64)]) -> f64 {
+    let mut values = vec![0.0; 1 << order.len()];
+    for unused in 1..values.len() {
+        let open: Vec<usize> = (0..order.len())
+            .filter(|index| unused & (1 << index) != 0)
+            .collect();
+        values[unused] = rolls
+            .iter()
+            .map(|(dice, probability)| {
+                let chosen = open
+                    .iter()
+                    .copied()
+                    .find(|index| check(order[*index], dice) != CombinationResult::NotMatched)
+                    .unwrap_or(open[open.len() - 1]);
+                probability
+                    * (score_or_zero(order[chosen], dice) as f64 + values[unused & !(1 << chosen)])
+            })
+            .sum();
+    }
+    values[values.len() - 1]
+}
+
+/// The greedy game's total minus the sum of each roll's own best score.
+/// The rolls set that ceiling, so 0 means every roll scored its best and a
+/// negative value is what the play left behind.
+fn luck_adjusted_score(rounds: &[Dice]) -> f64 {
+    calculate_yams_total_score_skip(rounds) as f64 - calculate_yams_total_score(rounds) as f64
+}
+
+fn all_rolls() -> Vec<Dice> {
+    joker_substitutions(&[JokerDie::Joker; 5])
+}
+
+fn all_roll_multisets() -> Vec<Dice> {
+    let mut multisets: Vec<Dice> = all_rolls().iter().map(canonicalize).collect();
+    multisets.sort();
+    multisets.dedup();
+    multisets
+}
+
+fn rolls_matching(combination: Combination) -> Vec<Dice> {
+    all_roll_multisets()
+        .into_iter()
+        .filter(|dice| check(combination, dice) != CombinationResult::NotMatched)
+        .collect()
+}
+
+fn dice_needed(dice: &Dice, combination: Combination) -> u8 {
+    let mut counts = [0u8; 7];
+    for die in dice {
+        counts[*die as usize] += 1;
+    }
+    rolls_matching(combination)
+        .iter()
+        .map(|target| {
+            let mut target_counts = [0u8; 7];
+            for die in target {
+                target_counts[*die as usize] += 1;
+            }
+            let kept: u8 = (1..=6)
+                .map(|face| counts[face].min(target_counts[face]))
+                .sum();
+            dice.len() as u8 - kept
+        })
+        .min()
+        .expect("every combination has at least one matching roll")
+}
+
+fn calculate_yams_round_result_with_jokers(dice: &JokerDice) -> (Combination, u32) {
+    joker_substitutions(dice)
+        .iter()
+        .map(calculate_yams_round_result)
+        .max_by_key(|(_, score)| *score)
+        .expect("every roll has at least one substitution")
+}
+
+fn best_possible_score(dice: &[Option<u8>; 5], remaining: &[Combination]) -> u32 {
+    let jokers = dice.map(|die| die.map_or(JokerDie::Joker, JokerDie::Value));
+    joker_substitutions(&jokers)
+        .iter()
+        .map(|dice| best_available_score(dice, remaining))
+        .max()
+        .unwrap_or(0)
+}
+
+fn joker_substitutions(dice: &JokerDice) -> Vec<Dice> {
+    let mut substitutions = vec![[0; 5]];
+    for (position, die) in dice.iter().enumerate() {
+        let faces = match *die {
+            JokerDie::Value(face) => face..=face,
+            JokerDie::Joker => 1..=6,
+        };
+        substitutions = substitutions
+            .into_iter()
+            .flat_map(|dice| {
+                faces.clone().map(move |face| {
+                    let mut next = dice;
+                    next[position] = face;
+                    next
+                })
+            })
+            .collect();
+    }
+    substitutions
+}
+
+fn optimal_first_keep(dice: &Dice, remaining: &[Combination], ruleset: &Ruleset) -> Vec<bool> {
+    RerollSolver::new(remaining)
+        .best_keep(dice, ruleset.rerolls_per_turn)
+        .0
+}
+
+fn suggest_reroll_indices(dice: &Dice, remaining: &[Combination], ruleset: &Ruleset) -> Vec<usize> {
+    RerollSolver::new(remaining)
+        .best_keep(dice, ruleset.rerolls_per_turn)
+        .0
+        .iter()
+        .enumerate()
+        .filter(|(_, keep)| !**keep)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn prob_reach_at_least(target: u32, dice: &Dice, remaining: &[Combination], rerolls: u8) -> f64 {
+    RerollSolver::with_final_value(|dice| {
+        if best_available_score(dice, remaining) >= target {
+            1.0
+        } else {
+            0.0
+        }
+    })
+    .expected_roll_value(dice, rerolls)
+}
+
+fn reroll_value(dice: &Dice, remaining: &[Combination], current_rerolls: u8) -> f64 {
+    let mut solver = RerollSolver::new(remaining);
+    solver.expected_roll_value(dice, current_rerolls + 1)
+        - solver.expected_roll_value(dice, current_rerolls)
+}
+
+/// `rerolls` counts the reroll of the dice not kept, so with 0 there is no
+/// roll to take an expectation over and the result is `None`. More than
+/// five kept dice, or a kept face outside 1 to 6, is also `None`.
+fn expected_score_after_keep(kept: &[u8], remaining: &[Combination], rerolls: u8) -> Option<f64> {
+    RerollSolver::new(remaining).expected_score_after_keep(kept, rerolls)
+}
+
+struct RerollSolver<'a> {
+    final_value: Box<dyn Fn(&Dice) -> f64 + 'a>,
+    memo: HashMap<(Vec<u8>, u8), f64>,
+    value_memo: HashMap<(Dice, u8), f64>,
+}
+
+impl<'a> RerollSolver<'a> {
+    fn new(remaining: &'a [Combination]) -> Self {
+        RerollSolver::with_final_value(move |dice| best_available_score(dice, remaining) as f64)
+    }
+
+    fn with_final_value(final_value: impl Fn(&Dice) -> f64 + 'a) -> Self {
+        RerollSolver {
+            final_value: Box::new(final_value),
+            memo: HashMap::new(),
+            value_memo: HashMap::new(),
+        }
+    }
+
+    fn best_keep(&mut self, dice: &Dice, rerolls: u8) -> (Vec<bool>, f64) {
+        let Some(rerolls_after) = rerolls.checked_sub(1) else {
+            return (vec![true; 5], (self.final_value)(dice));
+        };
+        let mut best: Option<(Vec<bool>, f64)> = None;
+        for mask in keep_masks() {
+            let kept = kept_faces(dice, &mask);
+            let expected = self.expected_after_rolling(&kept, rerolls_after);
+            if best
+                .as_ref()
+                .is_none_or(|(_, best_expected)| expected > *best_expected)
+            {
+                best = Some((mask, expected));
+            }
+        }
+        best.expect("there is always at least one keep mask")
+    }
+
+    fn expected_score_after_keep(&mut self, kept: &[u8], rerolls: u8) -> Option<f64> {
+        let rerolls_after = rerolls.checked_sub(1)?;
+        if kept.len() > 5 || kept.iter().any(|face| !(1..=6).contains(face)) {
+            return None;
+        }
+        Some(self.expected_after_rolling(kept, rerolls_after))
+    }
+
+    fn expected_after_rolling(&mut self, kept: &[u8], rerolls_after: u8) -> f64 {
+        let mut kept = kept.to_vec();
+        kept.sort();
+        if let Some(&expected) = self.memo.get(&(kept.clone(), rerolls_after)) {
+            return expected;
+        }
+
+        let expected = ROLL_OUTCOMES[5 - kept.len()]
+            .iter()
+            .map(|(rolled, probability)| {
+                let dice = combine_faces(&kept, rolled);
+                probability * self.expected_roll_value(&dice, rerolls_after)
+            })
+            .sum();
+        self.memo.insert((kept, rerolls_after), expected);
+        expected
+    }
+
+    fn expected_roll_value(&mut self, dice: &Dice, rerolls: u8) -> f64 {
+        let key = (canonicalize(dice), rerolls);
+        if let Some(&value) = self.value_memo.get(&key) {
+            return value;
+        }
+
+        let value = if rerolls == 0 {
+            (self.final_value)(dice)
+        } else {
+            self.best_keep(dice, rerolls).1
+        };
+        self.value_memo.insert(key, value);
+        value
+    }
+}
+
+fn optimal_expected_game_score() -> f64 {
+    optimal_expected_game_score_with_ruleset(&Ruleset::default())
+}
+
+fn optimal_expected_game_score_with_ruleset(ruleset: &Ruleset) -> f64 {
+    optimal_expected_remaining_score(&ruleset.combinations, ruleset.rerolls_per_turn)
+}
+
+fn expected_final_score(current_total: u32, remaining: &[Combination], ruleset: &Ruleset) -> f64 {
+    current_total as f64 + optimal_expected_remaining_score(remaining, ruleset.rerolls_per_turn)
+}
+
+fn optimal_expected_remaining_score(remaining: &[Combination], rerolls_per_turn: u8) -> f64 {
+    let values = remaining_score_values(remaining, rerolls_per_turn);
+    values[values.len() - 1]
+}
+
+fn remaining_score_values(remaining: &[Combination], rerolls_per_turn: u8) -> Vec<f64> {
+    let mut values = vec![0.0; 1 << remaining.len()];
+    for unused in 1..values.len() {
+        let turn_value = {
+            let mut solver = RerollSolver::with_final_value(|dice| {
+                (0..remaining.len())
+                    .filter(|index| unused & (1 << index) != 0)
+                    .map(|index| {
+                        score_or_zero(remaining[index], dice) as f64
+                            + values[unused & !(1 << index)]
+                    })
+                    .fold(f64::MIN, f64::max)
+            });
+            solver.expected_after_rolling(&[], rerolls_per_turn)
+        };
+        values[unused] = turn_value;
+    }
+    values
+}
+
+fn prob_final_score(target: u32, ruleset: &Ruleset) -> f64 {
+    final_score_distribution(ruleset)
+        .get(&target)
+        .copied()
+        .unwrap_or(0.0)
+}
+
+/// The distribution of the game's total when every keep and every play
+/// maximizes expected score, as in `optimal_expected_game_score`.
+fn final_score_distribution(ruleset: &Ruleset) -> HashMap<u32, f64> {
+    let remaining = &ruleset.combinations;
+    let values = remaining_score_values(remaining, ruleset.rerolls_per_turn);
+    let mut distributions = vec![HashMap::from([(0, 1.0)]); values.len()];
+    for unused in 1..values.len() {
+        let play = |dice: &Dice| best_final_play(dice, remaining, unused, &values);
+        let mut turn = TurnOutcomes {
+            solver: RerollSolver::with_final_value(|dice| {
+                let (index, score) = play(dice);
+                score as f64 + values[unused & !(1 << index)]
+            }),
+            play: &play,
+            memo: HashMap::new(),
+        };
+        let mut distribution = HashMap::new();
+        for ((index, score), probability) in turn.after_keep(&[], ruleset.rerolls_per_turn + 1) {
+            for (points, rest) in &distributions[unused & !(1 << index)] {
+                *distribution.entry(points + score).or_insert(0.0) += probability * rest;
+            }
+        }
+        distributions[unused] = distribution;
+    }
+    distributions
+        .pop()
+        .expect("there is always the empty set of combinations")
+}
+
+fn best_final_play(
+    dice: &Dice,
+    remaining: &[Combination],
+    unused: usize,
+    values: &[f64],
+) -> (usize, u32) {
+    (0..remaining.len())
+        .filter(|index| unused & (1 << index) != 0)
+        .map(|index| (index, score_or_zero(remaining[index], dice)))
+        .max_by(|a, b| {
+            let value =
+                |(index, score): &(usize, u32)| *score as f64 + values[unused & !(1 << index)];
+            value(a).total_cmp(&value(b))
+        })
+        .expect("at least one combination is unused")
+}
+
+type PlayDistribution = HashMap<(usize, u32), f64>;
+
+struct TurnOutcomes<'a> {
+    solver: RerollSolver<'a>,
+    play: &'a dyn Fn(&Dice) -> (usize, u32),
+    memo: HashMap<(Vec<u8>, u8), PlayDistribution>,
+}
+
+impl TurnOutcomes<'_> {
+    fn after_keep(&mut self, kept: &[u8], rerolls: u8) -> PlayDistribution {
+        let mut kept = kept.to_vec();
+        kept.sort();
+        if let Some(outcomes) = self.memo.get(&(kept.clone(), rerolls)) {
+            return outcomes.clone();
+        }
+
+        let mut outcomes = HashMap::new();
+        for (rolled, probability) in &ROLL_OUTCOMES[5 - kept.len()] {
+            let dice = combine_faces(&kept, rolled);
+            for (play, play_probability) in self.after_roll(&dice, rerolls - 1) {
+                *outcomes.entry(play).or_insert(0.0) += probability * play_probability;
+            }
+        }
+        self.memo.insert((kept, rerolls), outcomes.clone());
+        outcomes
+    }
+
+    fn after_roll(&mut self, dice: &Dice, rerolls: u8) -> PlayDistribution {
+        if rerolls == 0 {
+            return HashMap::from([((self.play)(dice), 1.0)]);
+        }
+        let (mask, _) = self.solver.best_keep(dice, rerolls);
+        self.after_keep(&kept_faces(dice, &mask), rerolls)
+    }
+}
+
+fn best_scratch(remaining: &[Combination], ruleset: &Ruleset) -> Option<Combination> {
+    let values = remaining_score_values(remaining, ruleset.rerolls_per_turn);
+    let all = values.len() - 1;
+    (0..remaining.len())
+        .max_by(|a, b| values[all & !(1 << a)].total_cmp(&values[all & !(1 << b)]))
+        .map(|index| remaining[index])
+}
+
+fn dump_combination(
+    dice: &Dice,
+    remaining: &[Combination],
+    ruleset: &Ruleset,
+) -> Option<(Combination, u32)> {
+    let values = remaining_score_values(remaining, ruleset.rerolls_per_turn);
+    let all = values.len() - 1;
+    let mut best: Option<(Combination, u32, f64)> = None;
+    for (index, combination) in remaining.iter().enumerate() {
+        let score = score_or_zero(*combination, dice);
+        let value = score as f64 + values[all & !(1 << index)];
+        if best.is_none_or(|(_, _, best_value)| value > best_value) {
+            best = Some((*combination, score, value));
+        }
+    }
+    best.map(|(combination, score, _)| (combination, score))
+}
+
+/// Rerolling every die can produce any roll, so a single reroll is enough
+/// to reach any combination.
+fn achievable_with_rolls(combination: Combination, starting_dice: &Dice, rerolls: u8) -> bool {
+    rerolls > 0 || check(combination, starting_dice) != CombinationResult::NotMatched
+}
+
+/// The fewest rerolls after which the combination has a non-zero chance
+/// of being made, within one turn's budget.
+fn min_rerolls_to_chance(
+    combination: Combination,
+    starting: &Dice,
+    ruleset: &Ruleset,
+) -> Option<u8> {
+    (0..=ruleset.rerolls_per_turn)
+        .find(|rerolls| achievable_with_rolls(combination, starting, *rerolls))
+}
+
+fn play_variance(dice: &Dice, combination: Combination, rerolls: u8) -> f64 {
+    let remaining = [combination];
+    let mut solver = RerollSolver::new(&remaining);
+    let (mean, mean_square) = score_moments(&mut solver, dice, rerolls);
+    mean_square - mean * mean
+}
+
+fn score_moments(solver: &mut RerollSolver, dice: &Dice, rerolls: u8) -> (f64, f64) {
+    if rerolls == 0 {
+        let score = (solver.final_value)(dice);
+        return (score, score * score);
+    }
+
+    let (mask, _) = solver.best_keep(dice, rerolls);
+    let kept = kept_faces(dice, &mask);
+    ROLL_OUTCOMES[5 - kept.len()]
+        .iter()
+        .map(|(rolled, probability)| {
+            let (mean, mean_square) =
+                score_moments(solver, &combine_faces(&kept, rolled), rerolls - 1);
+            (probability * mean, probability * mean_square)
+        })
+        .fold((0.0, 0.0), |(mean, mean_square), (m, ms)| {
+            (mean + m, mean_square + ms)
+        })
+}
+
+fn keep_masks() -> Vec<Vec<bool>> {
+    (0..32u8)
+        .rev()
+        .map(|bits| (0..5).map(|position| bits >> position & 1 == 1).collect())
+        .collect()
+}
+
+fn kept_faces(dice: &Dice, mask: &[bool]) -> Vec<u8> {
+    dice.iter()
+        .zip(mask)
+        .filter(|(_, keep)| **keep)
+        .map(|(die, _)| *die)
+        .collect()
+}
+
+fn combine_faces(kept: &[u8], rolled: &[u8]) -> Dice {
+    let mut dice = [0; 5];
+    dice[..kept.len()].copy_from_slice(kept);
+    dice[kept.len()..].copy_from_slice(rolled);
+    dice
+}
+
+fn roll_outcomes(count: usize) -> Vec<RollOutcome> {
+    let mut rolls: Vec<Vec<u8>> = vec![vec![]];
+    for _ in 0..count {
+        rolls = rolls
+            .into_iter()
+            .flat_map(|roll| {
+                (1..=6).map(move |face| {
+                    let mut next = roll.clone();
+                    next.push(face);
+                    next
+                })
+            })
+            .collect();
+    }
+
+    let total = rolls.len() as f64;
+    let mut counts: HashMap<Vec<u8>, u32> = HashMap::new();
+    for mut roll in rolls {
+        roll.sort();
+        *counts.entry(roll).or_insert(0) += 1;
+    }
+    let mut outcomes: Vec<RollOutcome> = counts
+        .into_iter()
+        .map(|(roll, count)| (roll, count as f64 / total))
+        .collect();
+    outcomes.sort_by(|a, b| a.0.cmp(&b.0));
+    outcomes
+}
+
+#[cfg(test)]
+mod test {
+    use super::calculate_yams_round_score;
+
+    #[test]
+    fn test_three_of_a_kind() {
+        let dice = dice![3, 3, 3, 2, 5];
+        assert_eq!(calculate_yams_round_score(&dice), 28);
+    }
+
+    #[test]
+    fn test_four_of_a_kind() {
+        let dice = dice![4, 4, 4, 4, 1];
+        assert_eq!(calculate_yams_round_score(&dice), 35);
+    }
+
+    #[test]
+    fn test_full_house() {
+        let dice = dice![2, 2, 3, 3, 3];
+        assert_eq!(calculate_yams_round_score(&dice), 30);
+    }
+
+    #[test]
+    fn test_straight() {
+        let dice = dice![1, 2, 3, 4, 5];
+        assert_eq!(calculate_yams_round_score(&dice), 40);
+    }
+
+    #[test]
+    fn test_chance() {
+        let dice = dice![1, 2, 3, 4, 6];
+        assert_eq!(calculate_yams_round_score(&dice), 16);
+    }
+
+    #[test]
+    fn test_yams_total_score() {
+        let rounds = vec![
+            dice![3, 3, 3, 2, 5], // Three of a kind: 28
+            dice![4, 4, 4, 4, 1], // Four of a kind: 35
+            dice![2, 2, 3, 3, 3], // Full house: 30
+            dice![1, 2, 3, 4, 5], // Straight: 40
+            dice![1, 2, 3, 4, 6], // Chance: 16
+        ];
+        assert_eq!(super::calculate_yams_total_score(&rounds), 149);
+    }
+
+    #[test]
+    fn test_yams_total_score_with_hook() {
+        use super::{Combination, ScoringConfig};
+
+        let rounds = vec![[4, 4, 4, 4, 1], [1, 2, 3, 4, 6]];
+        let mut calls = Vec::new();
+        let total = super::calculate_yams_total_score_with_hook(
+            &rounds,
+            &ScoringConfig::default(),
+            |index, result| calls.push((index, result)),
+        );
+        assert_eq!(total, 51);
+        assert_eq!(
+            calls,
+            vec![
+                (0, Some((Combination::FourOfAKind, 35))),
+                (1, Some((Combination::Chance, 16)))
+            ]
+        );
+
+        let config = ScoringConfig {
+            chance_minimum: Some(20),
+            ..ScoringConfig::default()
+        };
+        let mut calls = Vec::new();
+        let total =
+            super::calculate_yams_total_score_with_hook(&rounds, &config, |index, result| {
+                calls.push((index, result))
+            });
+        assert_eq!(total, 35);
+        assert_eq!(
+            calls,
+            vec![(0, Some((Combination::FourOfAKind, 35))), (1, None)]
+        );
+    }
+
+    #[test]
+    fn test_joker_completes_four_of_a_kind() {
+        use super::{Combination, JokerDie};
+
+        let dice = [
+            JokerDie::Value(4),
+            JokerDie::Value(4),
+            JokerDie::Value(4),
+            JokerDie::Joker,
+            JokerDie::Value(1),
+        ];
+        assert_eq!(
+            super::calculate_yams_round_result_with_jokers(&dice),
+            (Combination::FourOfAKind, 35)
+        );
+    }
+
+    #[test]
+    fn test_all_rolls_count() {
+        assert_eq!(super::all_rolls().len(), 7776);
+    }
+
+    #[test]
+    fn test_expected_greedy_round_score() {
+        let expected = super::expected_greedy_round_score();
+        assert!(expected > 20.0 && expected < 21.0);
+    }
+
+    #[test]
+    fn test_chance_score_range() {
+        use super::{Combination, ScoringConfig};
+
+        assert_eq!(
+            Combination::Chance.score_range(&ScoringConfig::default()),
+            (5, 30)
+        );
+        let config = ScoringConfig {
+            chance_minimum: Some(17),
+            ..ScoringConfig::default()
+        };
+        assert_eq!(Combination::Chance.score_range(&config), (17, 30));
+    }
+
+    #[test]
+    fn test_fixed_combination_score_range() {
+        use super::{Combination, ScoringConfig};
+
+        assert_eq!(
+            Combination::FourOfAKind.score_range(&ScoringConfig::default()),
+            (35, 35)
+        );
+    }
+
+    #[test]
+    fn test_optimal_first_keep_keeps_three_of_a_kind() {
+        use super::{Combination, Ruleset};
+
+        let dice = [3, 3, 3, 2, 5];
+        let remaining = [
+            Combination::FourOfAKind,
+            Combination::FullHouse,
+            Combination::ThreeOfAKind,
+            Combination::Straight,
+            Combination::Chance,
+        ];
+        assert_eq!(
+            super::optimal_first_keep(&dice, &remaining, &Ruleset::default()),
+            vec![true, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn test_score_accumulator_matches_total_score() {
+        use super::{Combination, ScoreAccumulator};
+
+        let rounds = vec![[3, 3, 3, 2, 5], [4, 4, 4, 4, 1], [1, 2, 3, 4, 6]];
+        let mut accumulator = ScoreAccumulator::default();
+        assert_eq!(accumulator.add(&rounds[0]), (Combination::ThreeOfAKind, 28));
+        assert_eq!(accumulator.total(), 28);
+        accumulator.add(&rounds[1]);
+        accumulator.add(&rounds[2]);
+        assert_eq!(
+            accumulator.total(),
+            super::calculate_yams_total_score(&rounds)
+        );
+    }
+
+    #[test]
+    fn test_skip_and_scratch_totals_differ_on_unmatchable_roll() {
+        let rounds = vec![
+            [1, 2, 3, 4, 6], // Chance: 16
+            [1, 1, 2, 4, 6], // Unmatchable once chance is used
+            [3, 3, 3, 2, 5], // Three of a kind: 28, unless scratched
+        ];
+        assert_eq!(super::calculate_yams_total_score_skip(&rounds), 44);
+        assert_eq!(super::calculate_yams_total_score_scratch(&rounds), 16);
+    }
+
+    #[test]
+    fn test_combination_usage_in_casual_game() {
+        use super::{Combination, ScoringConfig};
+
+        let rounds = vec![[3, 3, 3, 2, 5], [6, 6, 6, 1, 2], [1, 2, 3, 4, 6]];
+        let mut breakdown = Vec::new();
+        super::calculate_yams_total_score_with_hook(
+            &rounds,
+            &ScoringConfig::default(),
+            |_, result| breakdown.push(result),
+        );
+        breakdown.push(None);
+
+        let usage = super::combination_usage(&breakdown);
+        assert_eq!(usage[&Combination::ThreeOfAKind], 2);
+        assert_eq!(usage[&Combination::Chance], 1);
+        assert!(!usage.contains_key(&Combination::Straight));
+    }
+
+    #[test]
+    fn test_check_each_combination() {
+        use super::{Combination, CombinationResult, check};
+
+        assert_eq!(
+            check(Combination::FourOfAKind, &[4, 4, 4, 4, 1]),
+            CombinationResult::Matched(35)
+        );
+        assert_eq!(
+            check(Combination::FullHouse, &[2, 2, 3, 3, 3]),
+            CombinationResult::Matched(30)
+        );
+        assert_eq!(
+            check(Combination::ThreeOfAKind, &[3, 3, 3, 2, 5]),
+            CombinationResult::Matched(28)
+        );
+        assert_eq!(
+            check(Combination::Straight, &[2, 3, 4, 5, 6]),
+            CombinationResult::Matched(40)
+        );
+        assert_eq!(
+            check(Combination::Chance, &[1, 2, 3, 4, 6]),
+            CombinationResult::Matched(16)
+        );
+        assert_eq!(
+            check(Combination::Straight, &[1, 2, 3, 4, 6]),
+            CombinationResult::NotMatched
+        );
+    }
+
+    #[test]
+    fn test_score_round_from_counts_matches_dice() {
+        use super::ORDERED_COMBINATIONS;
+
+        let dice = [4, 4, 4, 4, 1];
+        let mut counts = [0; 7];
+        for die in dice {
+            counts[die as usize] += 1;
+        }
+        assert_eq!(
+            super::score_round_from_counts(&counts, &ORDERED_COMBINATIONS),
+            super::calculate_yams_round_result_among(&dice, &ORDERED_COMBINATIONS)
+        );
+        assert_eq!(
+            super::score_round_from_counts(&[0, 1, 0, 0, 4, 0, 0], &[super::Combination::Chance]),
+            Some((super::Combination::Chance, 17))
+        );
+    }
+
+    #[test]
+    fn test_straight_is_riskier_than_chance() {
+        use super::{Combination, play_variance};
+
+        let dice = [1, 2, 3, 4, 6];
+        let straight = play_variance(&dice, Combination::Straight, 2);
+        let chance = play_variance(&dice, Combination::Chance, 2);
+        assert!(straight > chance);
+        assert!(chance > 0.0);
+    }
+
+    #[test]
+    fn test_canonicalize_is_order_independent_and_idempotent() {
+        use super::canonicalize;
+
+        let canonical = canonicalize(&[5, 3, 2, 3, 3]);
+        assert_eq!(canonical, [2, 3, 3, 3, 5]);
+        assert_eq!(canonicalize(&[3, 3, 5, 2, 3]), canonical);
+        assert_eq!(canonicalize(&canonical), canonical);
+    }
+
+    #[test]
+    fn test_tie_break_policies() {
+        use super::{
+            Combination, PreferFirst, PreferHigherValue, PreferLowerValue,
+            calculate_yams_round_result_with_tie_break,
+        };
+
+        let dice = [6, 6, 6, 5, 5];
+        let remaining = [Combination::ThreeOfAKind, Combination::Chance];
+        assert_eq!(
+            calculate_yams_round_result_with_tie_break(&dice, &remaining, &PreferFirst),
+            Some((Combination::ThreeOfAKind, 28))
+        );
+        assert_eq!(
+            calculate_yams_round_result_with_tie_break(&dice, &remaining, &PreferHigherValue),
+            Some((Combination::Chance, 28))
+        );
+        assert_eq!(
+            calculate_yams_round_result_with_tie_break(&dice, &remaining, &PreferLowerValue),
+            Some((Combination::ThreeOfAKind, 28))
+        );
+    }
+
+    #[test]
+    fn test_forced_order_score_below_greedy() {
+        use super::{Combination, OrderLengthError};
+
+        let rounds = vec![[3, 3, 3, 2, 5], [1, 2, 3, 4, 5], [4, 4, 4, 4, 1]];
+        let matching_order = [
+            Combination::ThreeOfAKind,
+            Combination::Straight,
+            Combination::FourOfAKind,
+        ];
+        let bad_order = [
+            Combination::Straight,
+            Combination::FourOfAKind,
+            Combination::Chance,
+        ];
+        assert_eq!(
+            super::calculate_yams_forced_order_score(&rounds, &matching_order),
+            Ok(super::calculate_yams_total_score(&rounds))
+        );
+        assert_eq!(
+            super::calculate_yams_forced_order_score(&rounds, &bad_order),
+            Ok(17)
+        );
+        assert_eq!(
+            super::calculate_yams_forced_order_score(&rounds, &bad_order[..1]),
+            Err(OrderLengthError {
+                rounds: 3,
+                order: 1
+            })
+        );
+        assert_eq!(
+            super::calculate_yams_forced_order_score(&rounds[..2], &bad_order),
+            Err(OrderLengthError {
+                rounds: 2,
+                order: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_rolls_matching() {
+        use super::{Combination, rolls_matching};
+
+        assert_eq!(super::all_roll_multisets().len(), 252);
+        assert_eq!(
+            rolls_matching(Combination::Straight),
+            vec![[1, 2, 3, 4, 5], [2, 3, 4, 5, 6]]
+        );
+        assert_eq!(rolls_matching(Combination::FullHouse).len(), 30);
+        assert_eq!(rolls_matching(Combination::FourOfAKind).len(), 36);
+    }
+
+    #[test]
+    fn test_round_result_with_no_available_combinations() {
+        assert_eq!(
+            super::calculate_yams_round_result_among(&[4, 4, 4, 4, 1], &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_wasted_points() {
+        let rounds = vec![
+            [6, 6, 6, 5, 4], // Greedy takes three of a kind (28) over chance (27)
+            [1, 1, 1, 2, 3], // ...leaving only chance (8) here instead of 28
+        ];
+        assert_eq!(super::calculate_yams_total_score_skip(&rounds), 36);
+        assert_eq!(super::calculate_yams_optimal_score(&rounds), 55);
+        assert_eq!(super::wasted_points(&rounds), 19);
+    }
+
+    #[test]
+    fn test_die_construction() {
+        use super::{DiceError, Die};
+
+        assert_eq!(Die::new(6).map(|die| die.value()), Ok(6));
+        assert_eq!(Die::new(0), Err(DiceError::OutOfRange(0)));
+        assert_eq!(Die::try_from(7), Err(DiceError::OutOfRange(7)));
+    }
+
+    #[test]
+    fn test_validate_dice() {
+        use super::{DiceError, dice_from_dies, validate_dice};
+
+        let dies = validate_dice(&[3, 3, 3, 2, 5]).unwrap();
+        assert_eq!(dice_from_dies(&dies), [3, 3, 3, 2, 5]);
+        assert_eq!(
+            validate_dice(&[3, 3, 9, 2, 5]),
+            Err(DiceError::OutOfRange(9))
+        );
+    }
+
+    #[test]
+    fn test_second_best() {
+        use super::{Combination, ORDERED_COMBINATIONS, second_best};
+
+        assert_eq!(
+            second_best(&[6, 6, 6, 6, 6], &ORDERED_COMBINATIONS),
+            Some((Combination::Chance, 30))
+        );
+        assert_eq!(second_best(&[1, 2, 3, 4, 6], &ORDERED_COMBINATIONS), None);
+    }
+
+    #[test]
+    fn test_chance_minimum() {
+        use super::{Combination, CombinationResult, ScoringConfig, check_with_config};
+
+        let config = ScoringConfig {
+            chance_minimum: Some(17),
+            ..ScoringConfig::default()
+        };
+        assert_eq!(
+            check_with_config(Combination::Chance, &[1, 2, 3, 5, 6], &config),
+            CombinationResult::Matched(17)
+        );
+        assert_eq!(
+            check_with_config(Combination::Chance, &[1, 2, 3, 4, 6], &config),
+            CombinationResult::NotMatched
+        );
+        assert_eq!(
+            check_with_config(
+                Combination::Chance,
+                &[1, 2, 3, 4, 6],
+                &ScoringConfig::default()
+            ),
+            CombinationResult::Matched(16)
+        );
+    }
+
+    #[test]
+    fn test_round_result_below_chance_minimum() {
+        use super::{ORDERED_COMBINATIONS, ScoringConfig};
+
+        let config = ScoringConfig {
+            chance_minimum: Some(17),
+            ..ScoringConfig::default()
+        };
+        assert_eq!(
+            super::calculate_yams_round_result_with_config(
+                &[1, 2, 3, 4, 6],
+                &ORDERED_COMBINATIONS,
+                &config
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_achievable_with_rolls() {
+        use super::{Combination, achievable_with_rolls};
+
+        let dice = [1, 2, 3, 4, 4];
+        assert!(achievable_with_rolls(Combination::Straight, &dice, 1));
+        assert!(!achievable_with_rolls(Combination::Straight, &dice, 0));
+        assert!(achievable_with_rolls(Combination::Chance, &dice, 0));
+    }
+
+    #[test]
+    fn test_possible_scores() {
+        use super::{Combination, ScoringConfig};
+
+        let config = ScoringConfig::default();
+        assert_eq!(
+            Combination::Chance.possible_scores(&config),
+            (5..=30).collect::<Vec<u32>>()
+        );
+        assert_eq!(Combination::FullHouse.possible_scores(&config), vec![30]);
+
+        let config = ScoringConfig {
+            chance_minimum: Some(20),
+            ..ScoringConfig::default()
+        };
+        assert_eq!(
+            Combination::Chance.possible_scores(&config),
+            (20..=30).collect::<Vec<u32>>()
+        );
+    }
+
+    #[test]
+    fn test_combination_table_matches_direct_checks() {
+        use super::{COMBINATION_TABLE, ORDERED_COMBINATIONS, all_roll_multisets, check};
+
+        let multisets = all_roll_multisets();
+        assert_eq!(COMBINATION_TABLE.len(), multisets.len());
+        for dice in multisets {
+            for combination in ORDERED_COMBINATIONS {
+                assert_eq!(
+                    COMBINATION_TABLE[&dice][combination as usize],
+                    check(combination, &dice)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_best_combination_distribution_sums_to_one() {
+        use super::Combination;
+
+        let distribution = super::best_combination_distribution();
+        let total: f64 = distribution.values().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!((distribution[&Combination::Straight] - 240.0 / 7776.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_expected_score_after_keep() {
+        use super::{Combination, expected_score_after_keep};
+
+        let remaining = [Combination::FourOfAKind];
+        let keep_four = expected_score_after_keep(&[6, 6, 6, 6], &remaining, 1);
+        let keep_none = expected_score_after_keep(&[], &remaining, 1);
+        assert_eq!(keep_four, Some(35.0));
+        assert!(keep_none.is_some_and(|expected| expected < 1.0));
+        assert_eq!(expected_score_after_keep(&[6, 6, 6], &remaining, 0), None);
+        assert_eq!(expected_score_after_keep(&[1; 6], &remaining, 1), None);
+        assert_eq!(expected_score_after_keep(&[6, 7], &remaining, 1), None);
+    }
+
+    #[test]
+    fn test_single_combination_game_is_one_turn() {
+        use super::{Combination, expected_score_after_keep, optimal_expected_remaining_score};
+
+        let remaining = [Combination::Chance];
+        assert!(
+            (optimal_expected_remaining_score(&remaining, 2)
+                - expected_score_after_keep(&[], &remaining, 3).unwrap())
+            .abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_optimal_expected_game_score() {
+        let expected = super::optimal_expected_game_score();
+        assert!((expected - 110.964).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_combination_frequencies_sorted() {
+        use super::{
+            ORDERED_COMBINATIONS, combination_frequencies, combination_frequencies_sorted,
+        };
+
+        let sorted = combination_frequencies_sorted();
+        let order: Vec<_> = sorted.iter().map(|(combination, _)| *combination).collect();
+        assert_eq!(order, ORDERED_COMBINATIONS.to_vec());
+        assert_eq!(sorted.last().unwrap().1, 1.0);
+
+        let frequencies = combination_frequencies();
+        for (combination, frequency) in sorted {
+            assert_eq!(frequencies[&combination], frequency);
+        }
+    }
+
+    #[test]
+    fn test_luck_adjusted_score() {
+        let rounds = vec![[1, 2, 3, 4, 5], [1, 1, 2, 4, 6]];
+        assert_eq!(super::luck_adjusted_score(&rounds), 0.0);
+
+        // The second straight finds its combination already used: 54 of 94.
+        let rounds = vec![[1, 2, 3, 4, 5], [1, 1, 2, 4, 6], [2, 3, 4, 5, 6]];
+        assert_eq!(super::luck_adjusted_score(&rounds), -40.0);
+        assert_eq!(super::luck_adjusted_score(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_combination_implies() {
+        use super::{Combination, CombinationResult, ORDERED_COMBINATIONS, check};
+
+        assert_eq!(
+            Combination::FourOfAKind.implies(),
+            &[Combination::ThreeOfAKind, Combination::Chance]
+        );
+        for dice in super::all_roll_multisets() {
+            for combination in ORDERED_COMBINATIONS {
+                if check(combination, &dice) == CombinationResult::NotMatched {
+                    continue;
+                }
+                for implied in combination.implies() {
+                    assert_ne!(check(*implied, &dice), CombinationResult::NotMatched);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_top_k_cases() {
+        use super::{Combination, ORDERED_COMBINATIONS, matching_cases, top_k_cases};
+
+        let cases = matching_cases(&[6, 6, 6, 6, 5], &ORDERED_COMBINATIONS);
+        assert_eq!(
+            top_k_cases(&cases, 3),
+            vec![
+                (Combination::FourOfAKind, 35),
+                (Combination::Chance, 29),
+                (Combination::ThreeOfAKind, 28),
+            ]
+        );
+        assert_eq!(top_k_cases(&cases, 1), vec![(Combination::FourOfAKind, 35)]);
+        assert_eq!(top_k_cases(&cases, 0), vec![]);
+    }
+
+    #[test]
+    fn test_min_rerolls_to_chance() {
+        use super::{Combination, Ruleset, min_rerolls_to_chance};
+
+        let ruleset = Ruleset::default();
+        assert_eq!(
+            min_rerolls_to_chance(Combination::Straight, &[1, 2, 3, 4, 4], &ruleset),
+            Some(1)
+        );
+        assert_eq!(
+            min_rerolls_to_chance(Combination::Straight, &[5, 4, 3, 2, 1], &ruleset),
+            Some(0)
+        );
+        let no_rerolls = Ruleset {
+            rerolls_per_turn: 0,
+            ..Ruleset::default()
+        };
+        assert_eq!(
+            min_rerolls_to_chance(Combination::Straight, &[1, 2, 3, 4, 4], &no_rerolls),
+            None
+        );
+    }
+
+    #[test]
+    fn test_roll_quality() {
+        use super::{Combination, ORDERED_COMBINATIONS, roll_quality};
+
+        assert_eq!(roll_quality(&[2, 3, 4, 5, 6], &ORDERED_COMBINATIONS), 1.0);
+        assert_eq!(
+            roll_quality(&[6, 6, 6, 6, 6], &ORDERED_COMBINATIONS),
+            35.0 / 40.0
+        );
+        let remaining = [Combination::FourOfAKind, Combination::Straight];
+        assert_eq!(roll_quality(&[1, 1, 2, 4, 6], &remaining), 0.0);
+        assert_eq!(roll_quality(&[1, 1, 2, 4, 6], &[]), 0.0);
+    }
+
+    #[test]
+    fn test_suggest_reroll_indices_complements_keep() {
+        use super::{ORDERED_COMBINATIONS, Ruleset, optimal_first_keep, suggest_reroll_indices};
+
+        let dice = [3, 3, 3, 2, 5];
+        let ruleset = Ruleset::default();
+        let keep = optimal_first_keep(&dice, &ORDERED_COMBINATIONS, &ruleset);
+        let reroll = suggest_reroll_indices(&dice, &ORDERED_COMBINATIONS, &ruleset);
+        assert_eq!(reroll, vec![3, 4]);
+        for (index, keep) in keep.iter().enumerate() {
+            assert_eq!(reroll.contains(&index), !keep);
+        }
+        assert_eq!(
+            suggest_reroll_indices(
+                &dice,
+                &ORDERED_COMBINATIONS,
+                &Ruleset {
+                    rerolls_per_turn: 0,
+                    ..ruleset
+                }
+            ),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn test_calculate_yams_game() {
+        use super::{Combination, ScoringConfig, calculate_yams_game};
+
+        let rounds = vec![[3, 3, 3, 2, 5], [1, 2, 3, 4, 6]];
+        let result = calculate_yams_game(&rounds, &ScoringConfig::default());
+        assert_eq!(result.total, super::calculate_yams_total_score(&rounds));
+        assert_eq!(
+            result.breakdown,
+            vec![
+                Some((Combination::ThreeOfAKind, 28)),
+                Some((Combination::Chance, 16))
+            ]
+        );
+
+        let config = ScoringConfig {
+            chance_minimum: Some(17),
+            ..ScoringConfig::default()
+        };
+        let result = calculate_yams_game(&rounds, &config);
+        assert_eq!(result.breakdown[1], None);
+        assert_eq!(
+            result.total,
+            result
+                .breakdown
+                .iter()
+                .flatten()
+                .map(|(_, score)| score)
+                .sum()
+        );
+    }
+
+    #[test]
+    fn test_prob_reach_at_least() {
+        use super::{ORDERED_COMBINATIONS, prob_reach_at_least};
+
+        assert_eq!(
+            prob_reach_at_least(10, &[3, 3, 3, 2, 5], &ORDERED_COMBINATIONS, 0),
+            1.0
+        );
+        assert!(prob_reach_at_least(15, &[1, 1, 2, 2, 4], &ORDERED_COMBINATIONS, 2) > 0.95);
+        let straight = prob_reach_at_least(40, &[1, 2, 3, 4, 6], &ORDERED_COMBINATIONS, 2);
+        assert!((straight - 11.0 / 36.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_summarize_game() {
+        use super::{ScoringConfig, calculate_yams_game, summarize_game};
+
+        let rounds = vec![[3, 3, 3, 2, 5], [1, 2, 3, 4, 5], [6, 6, 6, 1, 2]];
+        let summary = summarize_game(&calculate_yams_game(&rounds, &ScoringConfig::default()));
+        assert_eq!(
+            summary,
+            "Total: 96 points over 3 rounds. \
+             Best round: 2 (Straight, 40 points). \
+             Worst round: 1 (Three of a kind, 28 points). \
+             Highest-scoring combination: Three of a kind (56 points)."
+        );
+    }
+
+    #[test]
+    fn test_best_possible_score_with_unknown_faces() {
+        use super::{Combination, ORDERED_COMBINATIONS, best_possible_score};
+
+        assert_eq!(
+            best_possible_score(
+                &[Some(1), Some(2), None, Some(4), Some(5)],
+                &ORDERED_COMBINATIONS
+            ),
+            40
+        );
+        assert_eq!(
+            best_possible_score(
+                &[Some(6), Some(6), Some(6), None, Some(2)],
+                &[Combination::FourOfAKind]
+            ),
+            35
+        );
+        assert_eq!(best_possible_score(&[None; 5], &[]), 0);
+    }
+
+    #[test]
+    fn test_combinations_by_rarity() {
+        use super::Combination;
+
+        let rarity: Vec<_> = super::combinations_by_rarity()
+            .into_iter()
+            .map(|(combination, _)| combination)
+            .collect();
+        assert_eq!(rarity.first(), Some(&Combination::FourOfAKind));
+        assert_eq!(rarity.last(), Some(&Combination::Chance));
+    }
+
+    #[test]
+    fn test_straight_requires_all_dice() {
+        use super::{Combination, CombinationResult, ScoringConfig, check_with_config};
+
+        let dice = [1, 2, 3, 4, 4];
+        assert_eq!(
+            check_with_config(Combination::Straight, &dice, &ScoringConfig::default()),
+            CombinationResult::NotMatched
+        );
+        let config = ScoringConfig {
+            straight_requires_all_dice: false,
+            ..ScoringConfig::default()
+        };
+        assert_eq!(
+            check_with_config(Combination::Straight, &dice, &config),
+            CombinationResult::Matched(40)
+        );
+        assert_eq!(
+            check_with_config(Combination::Straight, &[1, 2, 3, 5, 6], &config),
+            CombinationResult::NotMatched
+        );
+    }
+
+    #[test]
+    fn test_expected_final_score_is_monotonic() {
+        use super::{Combination, Ruleset, expected_final_score};
+
+        let ruleset = Ruleset::default();
+        let full = expected_final_score(0, &super::ORDERED_COMBINATIONS, &ruleset);
+        let fewer = expected_final_score(
+            0,
+            &[
+                Combination::FullHouse,
+                Combination::Straight,
+                Combination::Chance,
+            ],
+            &ruleset,
+        );
+        let last = expected_final_score(0, &[Combination::Chance], &ruleset);
+        assert!(full > fewer && fewer > last);
+        assert_eq!(expected_final_score(50, &[], &ruleset), 50.0);
+        let no_rerolls = Ruleset {
+            rerolls_per_turn: 0,
+            ..ruleset
+        };
+        assert!(expected_final_score(0, &[Combination::Chance], &no_rerolls) < last);
+    }
+
+    #[test]
+    fn test_best_cases_keeps_ties() {
+        use super::{Combination, best_cases, matching_cases};
+
+        let remaining = [Combination::ThreeOfAKind, Combination::Chance];
+        let cases = matching_cases(&[6, 6, 6, 5, 5], &remaining);
+        assert_eq!(
+            best_cases(&cases),
+            (28, vec![Combination::ThreeOfAKind, Combination::Chance])
+        );
+        assert_eq!(best_cases(&[]), (0, vec![]));
+    }
+
+    #[test]
+    fn test_dice_macro() {
+        assert_eq!(dice![3, 3, 3, 2, 5], [3, 3, 3, 2, 5]);
+        assert_eq!(calculate_yams_round_score(&dice![4, 4, 4, 4, 1,]), 35);
+        assert!(!super::dice_in_range(&[0, 1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn test_greedy_regret_per_round() {
+        let rounds = vec![
+            dice![1, 1, 1, 1, 1],
+            dice![4, 6, 6, 6, 6], // Three of a kind and chance both score 28
+            dice![1, 1, 1, 2, 3], // Greedy already spent three of a kind
+        ];
+        assert_eq!(super::greedy_regret_per_round(&rounds), vec![0, 0, 20]);
+        assert_eq!(super::wasted_points(&rounds), 20);
+    }
+
+    #[test]
+    fn test_strategically_equivalent() {
+        use super::{Combination, ORDERED_COMBINATIONS, strategically_equivalent};
+
+        let a = dice![1, 1, 1, 2, 3];
+        let b = dice![1, 1, 1, 4, 5];
+        assert!(!strategically_equivalent(&a, &b, &ORDERED_COMBINATIONS));
+        assert!(strategically_equivalent(
+            &a,
+            &b,
+            &[Combination::ThreeOfAKind, Combination::Straight]
+        ));
+        assert!(strategically_equivalent(
+            &a,
+            &dice![3, 1, 2, 1, 1],
+            &ORDERED_COMBINATIONS
+        ));
+    }
+
+    #[test]
+    fn test_expected_chance_score() {
+        let rolls = super::all_rolls();
+        let enumerated = rolls.iter().map(super::calculate_chance_score).sum::<u32>() as f64
+            / rolls.len() as f64;
+        assert_eq!(super::expected_chance_score(5, 6), 17.5);
+        assert!((enumerated - 17.5).abs() < 1e-9);
+        assert_eq!(super::expected_chance_score(6, 6), 21.0);
+        assert_eq!(super::expected_chance_score(5, 8), 22.5);
+    }
+
+    #[test]
+    fn test_total_score_with_restricted_ruleset() {
+        use super::{Combination, Ruleset, calculate_yams_total_score_with_ruleset};
+
+        let rounds = vec![
+            dice![3, 3, 3, 2, 5], // Three of a kind: 28
+            dice![1, 2, 3, 4, 5], // Straight: 40
+            dice![1, 2, 3, 4, 6], // No chance in this ruleset: 0
+        ];
+        let ruleset = Ruleset::with_combinations(&[
+            Combination::FullHouse,
+            Combination::ThreeOfAKind,
+            Combination::Straight,
+        ]);
+        assert_eq!(
+            calculate_yams_total_score_with_ruleset(&rounds, &ruleset),
+            68
+        );
+        assert_eq!(
+            calculate_yams_total_score_with_ruleset(&rounds, &Ruleset::default()),
+            super::calculate_yams_total_score(&rounds)
+        );
+    }
+
+    #[test]
+    fn test_fixed_score_matches_checkers() {
+        use super::{CombinationResult, ORDERED_COMBINATIONS, check, fixed_score};
+
+        for combination in ORDERED_COMBINATIONS {
+            let scores = combination.possible_scores(&super::ScoringConfig::default());
+            match fixed_score(combination) {
+                Some(score) => assert_eq!(scores, vec![score]),
+                None => assert!(scores.len() > 1),
+            }
+        }
+        assert_eq!(
+            check(super::Combination::FullHouse, &dice![2, 2, 3, 3, 3]),
+            CombinationResult::Matched(fixed_score(super::Combination::FullHouse).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_game_efficiency() {
+        use super::{ScoringConfig, calculate_yams_game, game_efficiency};
+
+        let rounds = vec![dice![1, 2, 3, 4, 5], dice![1, 2, 3, 4, 6]];
+        let result = calculate_yams_game(&rounds, &ScoringConfig::default());
+        assert_eq!(super::max_possible_game_score(2), 80);
+        assert_eq!(game_efficiency(&result), 70.0);
+        assert_eq!(
+            game_efficiency(&calculate_yams_game(&[], &ScoringConfig::default())),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_best_of_window() {
+        use super::{Combination, ORDERED_COMBINATIONS, best_of};
+
+        let window = [
+            dice![1, 2, 3, 4, 6],
+            dice![4, 4, 4, 4, 1],
+            dice![3, 3, 3, 2, 5],
+        ];
+        assert_eq!(
+            best_of(&window, &ORDERED_COMBINATIONS),
+            Some((1, Combination::FourOfAKind, 35))
+        );
+        assert_eq!(best_of(&window, &[Combination::Straight]), None);
+    }
+
+    #[test]
+    fn test_dispatch_paths_agree_on_every_roll_and_subset() {
+        use super::{
+            Combination, CombinationResult, ORDERED_COMBINATIONS, all_rolls,
+            calculate_yams_round_result_among, check, score_round_from_counts,
+        };
+
+        let reference = |dice: &super::Dice, available: &[Combination]| {
+            let mut best: Option<(Combination, u32)> = None;
+            for combination in available {
+                if let CombinationResult::Matched(score) = check(*combination, dice)
+                    && best.is_none_or(|(_, best_score)| score > best_score)
+                {
+                    best = Some((*combination, score));
+                }
+            }
+            best
+        };
+
+        for dice in all_rolls() {
+            let mut counts = [0; 7];
+            for die in dice {
+                counts[die as usize] += 1;
+            }
+            for subset in 0..1 << ORDERED_COMBINATIONS.len() {
+                let available: Vec<Combination> = ORDERED_COMBINATIONS
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| subset & (1 << index) != 0)
+                    .map(|(_, combination)| *combination)
+                    .collect();
+                let expected = reference(&dice, &available);
+                assert_eq!(
+                    calculate_yams_round_result_among(&dice, &available),
+                    expected
+                );
+                assert_eq!(score_round_from_counts(&counts, &available), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dump_combination_for_junk_roll() {
+        use super::{Combination, ORDERED_COMBINATIONS, Ruleset, dump_combination};
+
+        assert_eq!(
+            dump_combination(
+                &dice![1, 2, 4, 5, 6],
+                &ORDERED_COMBINATIONS,
+                &Ruleset::default()
+            ),
+            Some((Combination::Chance, 18))
+        );
+        assert_eq!(
+            dump_combination(&dice![1, 2, 4, 5, 6], &[], &Ruleset::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_score_with_extra_die() {
+        use super::{Combination, Die, ORDERED_COMBINATIONS, score_with_extra_die};
+
+        assert_eq!(
+            score_with_extra_die(&dice![4, 4, 4, 1, 2], Die(4), &ORDERED_COMBINATIONS),
+            Some((Combination::FourOfAKind, 35))
+        );
+        assert_eq!(
+            score_with_extra_die(&dice![1, 2, 3, 5, 5], Die(4), &[Combination::Straight]),
+            Some((Combination::Straight, 40))
+        );
+        assert_eq!(
+            score_with_extra_die(&dice![1, 2, 3, 5, 5], Die(6), &[Combination::Chance]),
+            Some((Combination::Chance, 22))
+        );
+        assert_eq!(
+            score_with_extra_die(&dice![2, 2, 2, 3, 3], Die(3), &ORDERED_COMBINATIONS),
+            Some((Combination::FullHouse, 30))
+        );
+        assert_eq!(
+            score_with_extra_die(&dice![4, 4, 4, 4, 1], Die(1), &[Combination::FullHouse]),
+            Some((Combination::FullHouse, 30))
+        );
+    }
+
+    #[test]
+    fn test_extra_die_never_lowers_score() {
+        use super::{
+            Die, ORDERED_COMBINATIONS, all_roll_multisets, best_available_score,
+            score_with_extra_die,
+        };
+
+        for dice in all_roll_multisets() {
+            for subset in 0..1 << ORDERED_COMBINATIONS.len() {
+                let available: Vec<_> = ORDERED_COMBINATIONS
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| subset & (1 << index) != 0)
+                    .map(|(_, combination)| *combination)
+                    .collect();
+                let without = best_available_score(&dice, &available);
+                for extra in 1..=6 {
+                    let with = score_with_extra_die(&dice, Die(extra), &available)
+                        .map_or(0, |(_, score)| score);
+                    assert!(with >= without, "{dice:?} + {extra} with {available:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_combination_info() {
+        use super::{Combination, combination_info};
+
+        let info = combination_info(Combination::FullHouse);
+        assert_eq!(info.name, "Full house");
+        assert_eq!(info.fixed_score, Some(30));
+        assert!(info.description.contains("Three dice"));
+        assert_eq!(Combination::FullHouse.to_string(), info.name);
+        assert_eq!(combination_info(Combination::Chance).fixed_score, None);
+    }
+
+    #[test]
+    fn test_weighted_sixes_increase_four_of_a_kind() {
+        use super::{Combination, combination_frequencies, combination_frequencies_weighted};
+
+        let uniform = combination_frequencies_weighted(&[1.0 / 6.0; 6]);
+        let fair = combination_frequencies();
+        for (combination, probability) in &fair {
+            assert!((uniform[combination] - probability).abs() < 1e-9);
+        }
+
+        let loaded = combination_frequencies_weighted(&[0.1, 0.1, 0.1, 0.1, 0.1, 0.5]);
+        assert!(loaded[&Combination::FourOfAKind] > fair[&Combination::FourOfAKind] * 5.0);
+        assert!((loaded[&Combination::Chance] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_total_score_with_turns() {
+        use super::{Ruleset, Turn, TurnError, calculate_yams_total_score_with_turns};
+
+        let ruleset = Ruleset::default();
+        let turns = vec![
+            Turn {
+                rolls: vec![dice![1, 3, 3, 2, 5], dice![3, 3, 3, 2, 5]],
+            },
+            Turn {
+                rolls: vec![dice![1, 2, 3, 4, 6]],
+            },
+        ];
+        assert_eq!(
+            calculate_yams_total_score_with_turns(&turns, &ruleset),
+            Ok(44)
+        );
+
+        let too_many = vec![Turn {
+            rolls: vec![dice![1, 1, 1, 1, 1]; 4],
+        }];
+        assert_eq!(
+            calculate_yams_total_score_with_turns(&too_many, &ruleset),
+            Err(TurnError::TooManyRerolls {
+                turn: 0,
+                rerolls: 3,
+                allowed: 2
+            })
+        );
+        assert_eq!(
+            calculate_yams_total_score_with_turns(&[Turn { rolls: vec![] }], &ruleset),
+            Err(TurnError::NoRolls { turn: 0 })
+        );
+
+        let one_reroll = Ruleset {
+            rerolls_per_turn: 1,
+            ..Ruleset::default()
+        };
+        assert_eq!(
+            calculate_yams_total_score_with_turns(&turns, &one_reroll),
+            Ok(44)
+        );
+        let two_rerolls = vec![Turn {
+            rolls: vec![dice![1, 1, 1, 1, 1]; 3],
+        }];
+        assert_eq!(
+            calculate_yams_total_score_with_turns(&two_rerolls, &one_reroll),
+            Err(TurnError::TooManyRerolls {
+                turn: 0,
+                rerolls: 2,
+                allowed: 1
+            })
+        );
+        assert_eq!(
+            TurnError::TooManyRerolls {
+                turn: 0,
+                rerolls: 2,
+                allowed: 1
+            }
+            .to_string(),
+            "turn 0 has 2 rerolls, more than the 1 allowed"
+        );
+    }
+
+    #[test]
+    fn test_best_scratch_is_value_aware() {
+        use super::{Combination, Ruleset, ScoringConfig, best_scratch};
+
+        let remaining = [
+            Combination::FourOfAKind,
+            Combination::FullHouse,
+            Combination::ThreeOfAKind,
+            Combination::Straight,
+        ];
+        let naive = remaining
+            .iter()
+            .copied()
+            .min_by_key(|combination| combination.score_range(&ScoringConfig::default()).1);
+        assert_eq!(naive, Some(Combination::ThreeOfAKind));
+        assert_eq!(
+            best_scratch(&remaining, &Ruleset::default()),
+            Some(Combination::Straight)
+        );
+        assert_eq!(best_scratch(&[], &Ruleset::default()), None);
+    }
+
+    #[test]
+    fn test_straight_kind() {
+        use super::{StraightKind, straight_kind};
+
+        assert_eq!(
+            straight_kind(&dice![3, 1, 5, 2, 4]),
+            Some(StraightKind::Low)
+        );
+        assert_eq!(
+            straight_kind(&dice![6, 2, 4, 3, 5]),
+            Some(StraightKind::High)
+        );
+        assert_eq!(straight_kind(&dice![1, 2, 3, 4, 6]), None);
+    }
+
+    #[test]
+    fn test_fewer_rerolls_lower_expected_score() {
+        use super::{Combination, Ruleset, optimal_expected_game_score_with_ruleset};
+
+        let two_rerolls = Ruleset::with_combinations(&[Combination::Straight, Combination::Chance]);
+        let one_reroll = Ruleset {
+            rerolls_per_turn: 1,
+            ..two_rerolls.clone()
+        };
+        assert!(
+            optimal_expected_game_score_with_ruleset(&one_reroll)
+                < optimal_expected_game_score_with_ruleset(&two_rerolls)
+        );
+    }
+
+    #[test]
+    fn test_breakdown_total_matches_game_total() {
+        use super::{ScoringConfig, breakdown_total, calculate_yams_game};
+
+        let rounds = [
+            dice![4, 4, 4, 4, 1],
+            dice![1, 2, 3, 4, 5],
+            dice![1, 1, 2, 2, 4],
+        ];
+        let config = ScoringConfig {
+            chance_minimum: Some(15),
+            ..ScoringConfig::default()
+        };
+        let result = calculate_yams_game(&rounds, &config);
+        assert_eq!(breakdown_total(&result.breakdown), result.total);
+        assert_eq!(breakdown_total(&result.breakdown), 75);
+    }
+
+    #[test]
+    fn test_legal_plays_include_scratches() {
+        use super::{Combination, legal_plays};
+
+        let remaining = [
+            Combination::FullHouse,
+            Combination::Straight,
+            Combination::Chance,
+        ];
+        let plays = legal_plays(&dice![2, 2, 3, 3, 3], &remaining);
+        assert_eq!(plays.len(), remaining.len());
+        assert_eq!(
+            plays,
+            vec![
+                (Combination::FullHouse, 30),
+                (Combination::Straight, 0),
+                (Combination::Chance, 13),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reroll_value() {
+        use super::{Combination, ORDERED_COMBINATIONS, reroll_value};
+
+        assert!(reroll_value(&dice![1, 2, 4, 5, 6], &ORDERED_COMBINATIONS, 0) > 0.0);
+        assert!(reroll_value(&dice![1, 2, 4, 5, 6], &ORDERED_COMBINATIONS, 1) > 0.0);
+        assert_eq!(
+            reroll_value(&dice![2, 3, 4, 5, 6], &[Combination::Straight], 0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_parse_dice() {
+        use super::{ParseDiceError, parse_dice};
+
+        assert_eq!(parse_dice("3 3 3 2 5"), Ok([3, 3, 3, 2, 5]));
+        assert_eq!(parse_dice("1,2, 3,4,5"), Ok([1, 2, 3, 4, 5]));
+        assert_eq!(
+            parse_dice("1 2 3 4"),
+            Err(ParseDiceError::WrongCount { found: 4 })
+        );
+        assert_eq!(
+            parse_dice("1 2 7 4 5"),
+            Err(ParseDiceError::OutOfRange {
+                value: 7,
+                position: 2
+            })
+        );
+        assert_eq!(
+            parse_dice("1 2 x 4 5"),
+            Err(ParseDiceError::NotANumber {
+                token: "x".to_string()
+            })
+        );
+        assert_eq!(
+            ParseDiceError::OutOfRange {
+                value: 0,
+                position: 0
+            }
+            .to_string(),
+            "die 1 has value 0, which is not between 1 and 6"
+        );
+    }
+
+    #[test]
+    fn test_dice_needed() {
+        use super::{Combination, dice_needed};
+
+        assert_eq!(
+            dice_needed(&dice![2, 2, 3, 3, 3], Combination::FullHouse),
+            0
+        );
+        assert_eq!(
+            dice_needed(&dice![2, 2, 3, 3, 5], Combination::FullHouse),
+            1
+        );
+        assert_eq!(
+            dice_needed(&dice![1, 2, 3, 4, 6], Combination::FullHouse),
+            3
+        );
+        assert_eq!(dice_needed(&dice![1, 1, 1, 3, 5], Combination::Straight), 2);
+        assert_eq!(dice_needed(&dice![6, 6, 6, 6, 6], Combination::Straight), 4);
+        assert_eq!(dice_needed(&dice![1, 2, 3, 4, 6], Combination::Chance), 0);
+    }
+
+    #[test]
+    fn test_optimal_fill_order() {
+        use super::{Combination, ORDERED_COMBINATIONS, optimal_fill_order};
+
+        let order = optimal_fill_order();
+        assert_eq!(order, optimal_fill_order());
+        assert_eq!(order.len(), ORDERED_COMBINATIONS.len());
+        assert!(
+            ORDERED_COMBINATIONS
+                .iter()
+                .all(|combination| order.contains(combination))
+        );
+        assert_eq!(order.last(), Some(&Combination::Chance));
+        assert_eq!(
+            order,
+            vec![
+                Combination::ThreeOfAKind,
+                Combination::Straight,
+                Combination::FullHouse,
+                Combination::FourOfAKind,
+                Combination::Chance,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_house_allows_yams() {
+        use super::{Combination, CombinationResult, ScoringConfig, check_with_config};
+
+        let yams = dice![6, 6, 6, 6, 6];
+        assert_eq!(
+            check_with_config(Combination::FullHouse, &yams, &ScoringConfig::default()),
+            CombinationResult::NotMatched
+        );
+        let config = ScoringConfig {
+            full_house_allows_yams: true,
+            ..ScoringConfig::default()
+        };
+        assert_eq!(
+            check_with_config(Combination::FullHouse, &yams, &config),
+            CombinationResult::Matched(30)
+        );
+        assert_eq!(
+            check_with_config(Combination::FullHouse, &dice![6, 6, 6, 6, 1], &config),
+            CombinationResult::NotMatched
+        );
+    }
+
+    #[test]
+    fn test_guaranteed_remaining_score() {
+        use super::{Combination, ScoringConfig, guaranteed_remaining_score};
+
+        let remaining = [
+            Combination::FullHouse,
+            Combination::Straight,
+            Combination::Chance,
+        ];
+        assert_eq!(
+            guaranteed_remaining_score(&remaining, &ScoringConfig::default()),
+            5
+        );
+        let config = ScoringConfig {
+            chance_minimum: Some(15),
+            ..ScoringConfig::default()
+        };
+        assert_eq!(guaranteed_remaining_score(&remaining, &config), 0);
+        assert_eq!(
+            guaranteed_remaining_score(&[Combination::Straight], &ScoringConfig::default()),
+            0
+        );
+    }
+
+    #[test]
+    fn test_game_to_csv() {
+        use super::{ScoringConfig, calculate_yams_game, game_to_csv};
+
+        let rounds = [dice![3, 3, 3, 2, 5], dice![1, 1, 2, 2, 4]];
+        let config = ScoringConfig {
+            chance_minimum: Some(15),
+            ..ScoringConfig::default()
+        };
+        let result = calculate_yams_game(&rounds, &config);
+        assert_eq!(result.rounds, rounds);
+        let csv = game_to_csv(&result);
+        assert_eq!(
+            csv,
+            "round,dice,combination,score\n1,3 3 3 2 5,Three of a kind,28\n2,1 1 2 2 4,,0\n"
+        );
+    }
+
+    #[test]
+    fn test_score_round_multi() {
+        use super::{Combination, ScoringConfig, score_round_multi};
+
+        let small_straight = ScoringConfig {
+            straight_requires_all_dice: false,
+            ..ScoringConfig::default()
+        };
+        assert_eq!(
+            score_round_multi(
+                &dice![1, 2, 3, 4, 6],
+                &[ScoringConfig::default(), small_straight]
+            ),
+            vec![
+                Some((Combination::Chance, 16)),
+                Some((Combination::Straight, 40)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_decision_point() {
+        use super::{Combination, is_decision_point};
+
+        let remaining = [Combination::FourOfAKind, Combination::Chance];
+        assert!(is_decision_point(&dice![6, 6, 6, 6, 6], &remaining));
+        assert!(!is_decision_point(&dice![1, 1, 1, 1, 2], &remaining));
+        assert!(!is_decision_point(
+            &dice![6, 6, 6, 6, 6],
+            &[Combination::FourOfAKind]
+        ));
+    }
+
+    #[test]
+    fn test_weighted_score() {
+        use super::{WeightError, calculate_yams_weighted_score};
+
+        let rounds = [
+            dice![3, 3, 3, 2, 5],
+            dice![1, 2, 3, 4, 6],
+            dice![1, 2, 3, 4, 5],
+        ];
+        assert_eq!(
+            calculate_yams_weighted_score(&rounds, &[1.0, 1.5, 2.0]),
+            Ok(28.0 + 24.0 + 80.0)
+        );
+        assert_eq!(
+            calculate_yams_weighted_score(&rounds, &[1.0, 2.0]),
+            Err(WeightError::WrongCount {
+                rounds: 3,
+                weights: 2
+            })
+        );
+        assert_eq!(
+            calculate_yams_weighted_score(&rounds, &[1.0, f64::NAN, 2.0]),
+            Err(WeightError::NotFinite { round: 1 })
+        );
+        assert_eq!(
+            calculate_yams_weighted_score(&rounds, &[1.0, 1.0, f64::INFINITY]),
+            Err(WeightError::NotFinite { round: 2 })
+        );
+    }
+
+    #[test]
+    fn test_final_score_probabilities_sum_to_one() {
+        use super::{Ruleset, final_score_distribution, max_possible_game_score, prob_final_score};
+
+        let no_rerolls = Ruleset {
+            rerolls_per_turn: 0,
+            ..Ruleset::default()
+        };
+        let distribution = final_score_distribution(&no_rerolls);
+        let total: f64 = (0..=max_possible_game_score(5))
+            .filter_map(|target| distribution.get(&target))
+            .sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!((prob_final_score(60, &no_rerolls) - distribution[&60]).abs() < 1e-12);
+        assert_eq!(prob_final_score(1000, &no_rerolls), 0.0);
+    }
+
+    #[test]
+    fn test_final_score_distribution_matches_expected_score() {
+        use super::{Ruleset, final_score_distribution, optimal_expected_game_score};
+
+        let distribution = final_score_distribution(&Ruleset::default());
+        let total: f64 = distribution.values().sum();
+        let mean: f64 = distribution
+            .iter()
+            .map(|(points, probability)| *points as f64 * probability)
+            .sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!((mean - optimal_expected_game_score()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_sort_for_display() {
+        use super::{Combination, sort_for_display};
+
+        assert_eq!(
+            sort_for_display(&dice![1, 4, 4, 6, 4], Combination::ThreeOfAKind),
+            [4, 4, 4, 1, 6]
+        );
+        assert_eq!(
+            sort_for_display(&dice![6, 2, 2, 2, 2], Combination::FourOfAKind),
+            [2, 2, 2, 2, 6]
+        );
+        assert_eq!(
+            sort_for_display(&dice![2, 5, 2, 5, 5], Combination::FullHouse),
+            [5, 5, 5, 2, 2]
+        );
+        assert_eq!(
+            sort_for_display(&dice![5, 3, 4, 6, 2], Combination::Straight),
+            [2, 3, 4, 5, 6]
+        );
+    }
+
+    #[test]
+    fn test_round_result_among_out_of_range_dice() {
+        use super::{
+            Combination, ORDERED_COMBINATIONS, calculate_yams_round_result_among,
+            calculate_yams_total_score,
+        };
+
+        assert_eq!(
+            calculate_yams_round_result_among(&[7, 7, 7, 7, 7], &ORDERED_COMBINATIONS),
+            Some((Combination::FourOfAKind, 35))
+        );
+        assert_eq!(calculate_yams_total_score(&[[7, 7, 7, 7, 7]]), 35);
     }
 }